@@ -1,54 +1,326 @@
 use anyhow::Result;
-use clap::Parser;
+use chrono::{Local, NaiveTime};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Sample, SampleFormat};
 use dasp_sample::{FromSample};
 use dirs::home_dir;
+use log::{error, info, warn};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 #[derive(Parser)]
 #[command(name = "beep")]
 #[command(about = "Modern beep alternative with notifications")]
 struct Args {
-    /// Frequency in Hz
-    #[arg(short, long, default_value = "1000")]
-    frequency: f32,
+    /// Frequency in Hz, or several separated by commas to play a chord
+    #[arg(short, long, default_value = "1000", value_delimiter = ',')]
+    frequency: Vec<f32>,
+
+    /// Shift the frequency by this many semitones
+    #[arg(long, allow_negative_numbers = true)]
+    semitones: Option<f32>,
 
-    /// Length in milliseconds
-    #[arg(short, long, default_value = "200")]
+    /// Shift the frequency by this many cents (1/100 semitone)
+    #[arg(long, allow_negative_numbers = true)]
+    cents: Option<f32>,
+
+    /// Length in ms, or a duration like "2s"
+    #[arg(short, long, default_value = "200", value_parser = parse_millis)]
     length: u64,
 
+    /// Waveform of the tone, square and sawtooth cut through noise better
+    #[arg(short, long, value_enum, default_value = "sine")]
+    waveform: Waveform,
+
+    /// Amplitude of the tone from 0.0 to 1.0 (out-of-range values are clamped)
+    #[arg(long, default_value = "0.3", allow_negative_numbers = true, value_parser = parse_volume)]
+    volume: f32,
+
+    /// Scale the tone to this RMS level instead of a peak --volume, for even loudness across waveforms
+    #[arg(long, value_name = "RMS", conflicts_with = "volume", value_parser = parse_target_rms)]
+    target_rms: Option<f32>,
+
+    /// Linear fade in/out at each end of the tone, in ms, to avoid clicks
+    #[arg(long, value_name = "MS", default_value = "5")]
+    fade_ms: u64,
+
+    /// Stereo position from -1.0 (left) to 1.0 (right)
+    #[arg(long, default_value = "0.0", allow_negative_numbers = true, value_parser = parse_pan)]
+    pan: f32,
+
     /// Number of repetitions
     #[arg(short, long, default_value = "1")]
     repeats: u32,
 
-    /// Delay between repetitions in ms
-    #[arg(short, long, default_value = "100")]
+    /// Delay between repetitions in ms, or a duration like "1m30s"
+    #[arg(short, long, default_value = "100", value_parser = parse_millis)]
     delay: u64,
 
-    /// Message to send
+    /// Keep repeating the beep until Ctrl-C, ignoring --repeats
+    #[arg(long = "loop", alias = "repeat-until-interrupted", conflicts_with_all = ["output_fd", "output_file", "watch", "tail", "daemon"])]
+    loop_beep: bool,
+
+    /// Wait this long before alerting, e.g. "5m" for a kitchen timer
+    #[arg(long = "in", value_name = "DURATION", value_parser = parse_duration, conflicts_with_all = ["watch", "tail", "cron", "digest", "replay", "flush_queue", "flush_digest"])]
+    start_in: Option<Duration>,
+
+    /// Volume for each successive repeat, e.g. "0.3,0.6,1.0" (the last one holds)
+    #[arg(long, value_name = "LIST", value_delimiter = ',', value_parser = parse_gain)]
+    repeat_volumes: Vec<f32>,
+
+    /// Skip a beep identical to the previous one if it would start within MS of it ending
+    #[arg(long, value_name = "MS")]
+    skip_duplicate_window: Option<u64>,
+
+    /// Pause repeating beeps for this many seconds when Enter is pressed
+    #[arg(long, value_name = "SECONDS")]
+    snooze: Option<u64>,
+
+    /// Also snooze when this file is created (it is removed afterwards)
+    #[arg(long, requires = "snooze")]
+    snooze_file: Option<PathBuf>,
+
+    /// Serve an acknowledge link on ADDR (e.g. 0.0.0.0:8089) that stops the beep
+    #[arg(long, value_name = "ADDR")]
+    ack_listen: Option<SocketAddr>,
+
+    /// Public base URL for the acknowledge link, when ADDR isn't reachable as-is
+    #[arg(long, value_name = "URL", requires = "ack_listen")]
+    ack_url: Option<String>,
+
+    /// Stop serving the acknowledge link after this many seconds
+    #[arg(long, value_name = "SECONDS", default_value = "3600")]
+    ack_timeout: u64,
+
+    /// Load tone parameters from ~/.config/beep/presets/NAME.yaml
+    #[arg(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Melody of LEN:NOTE or NOTE:MS tokens, e.g. "q:C4 q:E4 h:G4" or "A4:200,C5:150,880:300" (R = rest)
+    #[arg(long, conflicts_with = "expr")]
+    melody: Option<String>,
+
+    /// Tempo for --melody note lengths, in quarter notes per minute
+    #[arg(long, default_value = "120", value_parser = clap::value_parser!(u32).range(1..))]
+    bpm: u32,
+
+    /// Allow notes outside the audible 20 Hz - 20 kHz range
+    #[arg(long)]
+    allow_inaudible: bool,
+
+    /// Custom tone as a math expression of time `t` in seconds, replaces the sine
+    #[arg(long)]
+    expr: Option<String>,
+
+    /// Sonify a 0-100 progress value: higher pitch and more beeps as it grows
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        value_parser = clap::value_parser!(u8).range(0..=100),
+        conflicts_with_all = ["frequency", "repeats", "melody"]
+    )]
+    progress: Option<u8>,
+
+    /// Message to send, "-" reads it from stdin
     #[arg(short = 'D', long)]
     data: Option<String>,
 
+    /// Cut a message read with -D - to this many characters
+    #[arg(long, value_name = "CHARS", default_value = "1024", value_parser = clap::value_parser!(u64).range(1..))]
+    max_message_length: u64,
+
+    /// Run this shell command and send its output as the message
+    #[arg(long, value_name = "CMD", conflicts_with = "data")]
+    data_cmd: Option<String>,
+
+    /// Send the contents of this file as the message, e.g. a prepared JSON document
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["data", "data_cmd"])]
+    message_file: Option<PathBuf>,
+
     /// Notification title
     #[arg(short, long)]
     title: Option<String>,
 
-    /// Priority (Pushover only: -2, -1, 0, 1, 2)
-    #[arg(short, long)]
+    /// Hard-wrap the message at this many columns, on word boundaries
+    #[arg(long, value_name = "COLUMNS", value_parser = clap::value_parser!(u32).range(1..))]
+    wrap: Option<u32>,
+
+    /// Format the message as markdown on providers that support it
+    #[arg(long)]
+    markdown: bool,
+
+    /// Priority (Pushover only: -2, -1, 0, 1, 2; 2 needs --retry and --expire)
+    #[arg(short, long, requires_ifs = [("2", "retry"), ("2", "expire")])]
     priority: Option<i8>,
 
+    /// Exit status of the command that ran before beep, e.g. $?; non-zero reports a failure
+    #[arg(long, value_name = "N", allow_negative_numbers = true)]
+    exit_code: Option<i32>,
+
+    /// Attach coordinates "LAT,LON" on providers that support it
+    #[arg(long, value_name = "LAT,LON", allow_hyphen_values = true, value_parser = parse_location)]
+    location: Option<Location>,
+
+    /// Stop notifying further providers once one has succeeded
+    #[arg(long)]
+    stop_on_success: bool,
+
+    /// Retry failed webhook/Pushover requests this many times, backing off from 500 ms
+    #[arg(long, default_value = "0", value_name = "N")]
+    retries: u32,
+
+    /// Give up on an HTTP request after SECONDS (sound downloads: SECONDS without data)
+    #[arg(long, default_value = "10", value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: u64,
+
+    /// Auto-delete the notification after SECONDS (Pushover only, priority < 2)
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+    ttl: Option<u32>,
+
+    /// Pushover sound to play on the receiving device, overriding pushover.sound
+    #[arg(long, value_name = "NAME")]
+    pushover_sound: Option<String>,
+
+    /// Re-alert an unacknowledged emergency (-p 2) Pushover notification every SECONDS
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(30..))]
+    retry: Option<u32>,
+
+    /// Stop re-alerting an emergency (-p 2) Pushover notification after SECONDS
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..=10800))]
+    expire: Option<u32>,
+
+    /// Severity, mapped to each provider's priority/urgency
+    #[arg(long, value_enum)]
+    severity: Option<Severity>,
+
     /// Don't play sound locally
     #[arg(long)]
     no_sound: bool,
 
+    /// Play a short blip for every provider that confirms delivery
+    #[arg(long)]
+    confirm_beep: bool,
+
+    /// Loop the configured sound file until an emergency (-p 2) Pushover alert is acknowledged
+    #[arg(long)]
+    repeat_until_ack: bool,
+
+    /// Play a bundled alert sound instead of the configured one
+    #[arg(long, value_enum)]
+    builtin_sound: Option<BuiltinSound>,
+
+    /// What to play instead when the sound fails and the beep is off
+    #[arg(long, value_enum, default_value = "beep")]
+    sound_fallback: SoundFallback,
+
+    /// Output device for the beep (name substring), instead of the default
+    #[arg(long, value_name = "NAME")]
+    device: Option<String>,
+
+    /// Output device for sound files (name substring), separate from the beep
+    #[arg(long, value_name = "NAME")]
+    sound_device: Option<String>,
+
+    /// Show the playback progress of sound files on stderr
+    #[arg(long)]
+    sound_progress: bool,
+
+    /// Volume of sound files from 0.0 to 1.0, independent of --volume [default: sound.volume or 1.0]
+    #[arg(long, allow_negative_numbers = true, value_parser = parse_volume)]
+    sound_volume: Option<f32>,
+
+    /// Play the sound file at the same time as the beep instead of before it
+    #[arg(long)]
+    layer: bool,
+
+    /// Master gain applied to both the beep and sound playback
+    #[arg(long, default_value = "1.0", value_parser = parse_gain)]
+    gain: f32,
+
+    /// Audio system used for the beep
+    #[arg(long, value_enum, default_value = "default")]
+    audio_host: AudioHost,
+
+    /// Request an audio buffer of this many ms, more latency but fewer underruns
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    buffer_ms: Option<u64>,
+
+    /// Printed instead of the beep when there is no audio, escapes like \a, \e and \x07 allowed
+    #[arg(long, default_value = "\\a", value_name = "STRING", value_parser = parse_bell_char)]
+    bell_char: String,
+
+    /// Synthesize the beep in memory without opening an audio device (CI/testing)
+    #[arg(long)]
+    headless: bool,
+
+    /// Write raw PCM (s16le, 44100 Hz, mono) to this file descriptor instead of playing
+    #[arg(long, value_name = "FD")]
+    output_fd: Option<i32>,
+
+    /// Write the beep to a WAV file (16-bit, 44100 Hz, mono) instead of playing it
+    #[arg(long, value_name = "PATH", conflicts_with = "output_fd")]
+    output_file: Option<PathBuf>,
+
+    /// Watch a file or directory and alert on every change
+    #[arg(long, value_name = "PATH")]
+    watch: Option<PathBuf>,
+
+    /// Quiet period in ms before a burst of --watch changes or --tail matches fires
+    #[arg(long, default_value = "500")]
+    watch_debounce: u64,
+
+    /// Follow a log file (through rotation) and alert on lines matching --match
+    #[arg(long, value_name = "FILE", requires = "match_regex", conflicts_with = "watch")]
+    tail: Option<PathBuf>,
+
+    /// Regex a --tail line has to match, the matching line becomes the message
+    #[arg(long = "match", value_name = "REGEX", requires = "tail")]
+    match_regex: Option<regex::Regex>,
+
+    /// Fire on a cron schedule, e.g. "*/30 * * * *" (requires --daemon)
+    #[arg(long, value_name = "EXPR", value_parser = parse_cron, requires = "daemon", conflicts_with_all = ["watch", "tail"])]
+    cron: Option<croner::Cron>,
+
+    /// Keep running and alert on every --cron tick until interrupted
+    #[arg(long, requires = "cron")]
+    daemon: bool,
+
+    /// Write Prometheus textfile-collector metrics about delivery to PATH
+    #[arg(long, value_name = "PATH")]
+    metrics_file: Option<PathBuf>,
+
+    /// Queue the message for a later --flush-digest instead of alerting now
+    #[arg(long, conflicts_with_all = ["flush_digest", "watch", "tail"])]
+    digest: bool,
+
+    /// Send all queued digest messages as one notification
+    #[arg(long, conflicts_with_all = ["data", "watch", "tail"])]
+    flush_digest: bool,
+
+    /// Send the last notification again, to every configured provider
+    #[arg(long, conflicts_with_all = ["data", "data_cmd", "digest", "flush_digest", "watch", "tail", "cron"])]
+    replay: bool,
+
+    /// Only replay to this provider
+    #[arg(long, value_enum, value_name = "PROVIDER", requires = "replay")]
+    replay_to: Option<Provider>,
+
+    /// Retry notifications that failed in earlier runs, then exit
+    #[arg(long, conflicts_with_all = ["data", "data_cmd", "digest", "flush_digest", "replay", "watch", "tail", "cron"])]
+    flush_queue: bool,
+
     /// Path to configuration file
     #[arg(short, long)]
     config: Option<PathBuf>,
@@ -57,349 +329,6187 @@ struct Args {
     #[arg(long)]
     sample_config: bool,
 
+    /// Write the sample configuration to ~/.config/beep.yaml, unless it already exists
+    #[arg(long, conflicts_with = "config")]
+    config_init: bool,
+
+    /// Validate the configuration and show a per-provider summary
+    #[arg(long)]
+    check_config: bool,
+
+    /// Print the loaded configuration as YAML, e.g. to migrate a TOML/JSON config
+    #[arg(long)]
+    dump_config: bool,
+
+    /// Replace tokens, keys and webhook URLs with "********" in --dump-config
+    #[arg(long, requires = "dump_config")]
+    mask_secrets: bool,
+
+    /// List the available output devices, for --device
+    #[arg(long)]
+    list_devices: bool,
+
+    /// Measure audio startup latency of each output device
+    #[arg(long)]
+    latency_report: bool,
+
+    /// Check that the configured sound file/URL decodes, without playing it
+    #[arg(long)]
+    check_sound: bool,
+
+    /// Interactive editor to try tones and notifications and save them as a preset
+    #[arg(long)]
+    tui: bool,
+
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
-}
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Config {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pushover: Option<PushoverConfig>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    webhook: Option<WebhookConfig>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    sound: Option<SoundConfig>,
-}
+    /// Send status and error messages to syslog instead of the console
+    #[arg(long)]
+    syslog: bool,
 
-#[derive(Debug, Deserialize, Serialize)]
-struct PushoverConfig {
-    api_token: String,
-    user_key: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    device: Option<String>,
-}
+    /// Read a numeric value from stdin (use with --threshold)
+    #[arg(long, requires = "threshold")]
+    stdin: bool,
 
-#[derive(Debug, Deserialize, Serialize)]
-struct WebhookConfig {
-    url: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    method: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    headers: Option<HashMap<String, String>>,
+    /// Only beep/notify if the stdin value matches, e.g. "> 80"
+    #[arg(long, requires = "stdin", value_parser = parse_threshold)]
+    threshold: Option<Threshold>,
+
+    /// Also POST the notification to this URL, no webhook config needed
+    #[arg(long, value_name = "URL", value_parser = parse_webhook_url)]
+    webhook_url: Option<String>,
+
+    /// Also pop up a desktop notification, like desktop.enabled in the config
+    #[arg(long)]
+    desktop: bool,
+
+    /// Wrap JSON webhook payloads with host/time/beep metadata
+    #[arg(long)]
+    include_meta: bool,
+
+    /// Add the beep's frequency, length and waveform to the message, so the recipient knows which tone fired
+    #[arg(long)]
+    include_tone_info: bool,
+
+    /// Don't verify webhook TLS certificates (UNSAFE, for testing only)
+    #[arg(long)]
+    insecure: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct SoundConfig {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    file: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    url: Option<String>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Info,
+    Warning,
+    Critical,
 }
 
-fn get_config_path(custom_path: Option<PathBuf>) -> PathBuf {
-    if let Some(path) = custom_path {
-        return path;
+impl Severity {
+    /// Pushover priority used when --priority isn't given
+    fn pushover_priority(self) -> i8 {
+        match self {
+            Severity::Info => -1,
+            Severity::Warning => 0,
+            Severity::Critical => 1,
+        }
     }
     
-    if let Some(home) = home_dir() {
-        home.join(".config").join("beep.yaml")
-    } else {
-        PathBuf::from("beep.yaml")
+    /// Discord embed color, blue/yellow/red
+    fn discord_color(self) -> u32 {
+        match self {
+            Severity::Info => 0x3498db,
+            Severity::Warning => 0xf1c40f,
+            Severity::Critical => 0xe74c3c,
+        }
     }
 }
 
-fn load_config(path: &PathBuf) -> Result<Option<Config>> {
-    if !path.exists() {
-        return Ok(None);
-    }
-    
-    let content = fs::read_to_string(path)?;
-    let config: Config = serde_yaml::from_str(&content)?;
-    Ok(Some(config))
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
 }
 
-fn print_sample_config() {
-    let sample = r#"# Modern Beep Configuration (~/.config/beep.yaml)
-# Pushover notifications
-pushover:
-  api_token: "your_api_token_here"
-  user_key: "your_user_key_here"
-  device: "optional_device_name"
+#[derive(Debug, Clone, Copy)]
+struct Threshold {
+    comparison: Comparison,
+    operand: f64,
+}
 
-# HTTP Webhook
-webhook:
-  url: "https://example.com/notifications"
-  method: "POST"  # optional, defaults to POST
-  headers:        # optional headers
-    Authorization: "Bearer your_token"
-    Content-Type: "application/json"
+impl Threshold {
+    fn matches(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Greater => value > self.operand,
+            Comparison::GreaterOrEqual => value >= self.operand,
+            Comparison::Less => value < self.operand,
+            Comparison::LessOrEqual => value <= self.operand,
+            Comparison::Equal => value == self.operand,
+            Comparison::NotEqual => value != self.operand,
+        }
+    }
+}
 
-# Sound file playback
-sound:
-  file: "/path/to/notification.wav"        # local file
-  url: "https://example.com/sound.mp3"     # or remote URL
-"#;
-    println!("{}", sample);
+fn parse_threshold(s: &str) -> Result<Threshold, String> {
+    let s = s.trim();
+    
+    // Two-character operators must be checked first
+    let (comparison, rest) = if let Some(rest) = s.strip_prefix(">=") {
+        (Comparison::GreaterOrEqual, rest)
+    } else if let Some(rest) = s.strip_prefix("<=") {
+        (Comparison::LessOrEqual, rest)
+    } else if let Some(rest) = s.strip_prefix("==") {
+        (Comparison::Equal, rest)
+    } else if let Some(rest) = s.strip_prefix("!=") {
+        (Comparison::NotEqual, rest)
+    } else if let Some(rest) = s.strip_prefix('>') {
+        (Comparison::Greater, rest)
+    } else if let Some(rest) = s.strip_prefix('<') {
+        (Comparison::Less, rest)
+    } else if let Some(rest) = s.strip_prefix('=') {
+        (Comparison::Equal, rest)
+    } else {
+        return Err(format!("expected an operator (>, >=, <, <=, ==, !=) in '{}'", s));
+    };
+    
+    let operand = rest
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number '{}'", rest.trim()))?;
+    
+    Ok(Threshold { comparison, operand })
 }
 
-async fn send_pushover_notification(
-    config: &PushoverConfig, 
-    message: &str, 
-    title: Option<&str>,
-    priority: Option<i8>,
-    verbose: bool
-) -> Result<()> {
-    let client = Client::new();
-    let mut params = HashMap::new();
+/// Where an alert came from, in decimal degrees
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+struct Location {
+    lat: f64,
+    lon: f64,
+}
+
+fn parse_location(s: &str) -> Result<Location, String> {
+    let (lat, lon) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected LAT,LON, got '{}'", s))?;
     
-    params.insert("token", config.api_token.clone());
-    params.insert("user", config.user_key.clone());
-    params.insert("message", message.to_string());
+    let lat: f64 = lat.trim().parse().map_err(|_| format!("invalid latitude '{}'", lat.trim()))?;
+    let lon: f64 = lon.trim().parse().map_err(|_| format!("invalid longitude '{}'", lon.trim()))?;
     
-    if let Some(title) = title {
-        params.insert("title", title.to_string());
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(format!("latitude {} is outside -90..90", lat));
     }
-    
-    if let Some(device) = &config.device {
-        params.insert("device", device.clone());
+    if !(-180.0..=180.0).contains(&lon) {
+        return Err(format!("longitude {} is outside -180..180", lon));
     }
+    Ok(Location { lat, lon })
+}
+
+fn parse_volume(s: &str) -> Result<f32, String> {
+    let volume = s
+        .parse::<f32>()
+        .map_err(|_| format!("invalid number '{}'", s))?;
     
-    if let Some(priority) = priority {
-        params.insert("priority", priority.to_string());
+    if !volume.is_finite() {
+        return Err(format!("volume must be a finite number, got '{}'", s));
     }
+    Ok(volume)
+}
+
+fn parse_target_rms(s: &str) -> Result<f32, String> {
+    let rms = s
+        .parse::<f32>()
+        .map_err(|_| format!("invalid number '{}'", s))?;
     
-    let response = client
-        .post("https://api.pushover.net/1/messages.json")
-        .form(&params)
-        .send()
-        .await?;
-    
-    if response.status().is_success() {
-        if verbose {
-            println!("✓ Pushover notification sent");
-        }
-    } else {
-        eprintln!("✗ Pushover error: {}", response.status());
+    if !(rms > 0.0 && rms <= 1.0) {
+        return Err(format!("target RMS must be above 0.0 and at most 1.0, got '{}'", s));
     }
-    
-    Ok(())
+    Ok(rms)
 }
 
-async fn send_webhook_notification(
-    config: &WebhookConfig, 
-    data: &str,
-    verbose: bool
-) -> Result<()> {
-    let client = Client::new();
-    let method = config.method.as_deref().unwrap_or("POST");
+fn parse_pan(s: &str) -> Result<f32, String> {
+    let pan = s
+        .parse::<f32>()
+        .map_err(|_| format!("invalid number '{}'", s))?;
     
-    let mut request = match method.to_uppercase().as_str() {
-        "GET" => client.get(&config.url),
-        "PUT" => client.put(&config.url),
-        "PATCH" => client.patch(&config.url),
-        _ => client.post(&config.url),
-    };
-    
-    // Próbuj sparsować jako JSON, jeśli się nie uda - wyślij jako tekst
-    if let Ok(json_value) = serde_json::from_str::<Value>(data) {
-        request = request.json(&json_value);
-    } else {
-        request = request.body(data.to_string());
+    if !(-1.0..=1.0).contains(&pan) {
+        return Err(format!("pan must be between -1.0 and 1.0, got '{}'", s));
     }
+    Ok(pan)
+}
+
+fn parse_gain(s: &str) -> Result<f32, String> {
+    let gain = s
+        .parse::<f32>()
+        .map_err(|_| format!("invalid number '{}'", s))?;
     
-    // Dodaj niestandardowe nagłówki
-    if let Some(headers) = &config.headers {
-        for (key, value) in headers {
-            request = request.header(key, value);
-        }
+    if !gain.is_finite() || gain < 0.0 {
+        return Err(format!("gain must be a non-negative number, got '{}'", s));
+    }
+    Ok(gain)
+}
+
+/// Milliseconds, as a bare number or a duration like "2s", "1500ms" or "1m30s"
+fn parse_millis(s: &str) -> Result<u64, String> {
+    if let Ok(ms) = s.parse::<u64>() {
+        return Ok(ms);
     }
     
-    let response = request.send().await?;
+    let duration = humantime::parse_duration(s)
+        .map_err(|e| format!("invalid duration '{}' ({}), use ms or e.g. \"2s\"", s, e))?;
+    u64::try_from(duration.as_millis()).map_err(|_| format!("duration '{}' is too long", s))
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    humantime::parse_duration(s).map_err(|e| format!("invalid duration '{}' ({})", s, e))
+}
+
+/// Unescape a --bell-char value: \a, \e, \n, \r, \t, \\ and \xHH
+fn parse_bell_char(s: &str) -> Result<String, String> {
+    let mut unescaped = String::new();
+    let mut chars = s.chars();
     
-    if response.status().is_success() {
-        if verbose {
-            println!("✓ Webhook sent to {}", config.url);
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('a') => unescaped.push('\x07'),
+            Some('e') => unescaped.push('\x1b'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('\\') => unescaped.push('\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|b| b.is_ascii() && hex.len() == 2)
+                    .ok_or_else(|| format!("invalid escape '\\x{}', expected two hex digits up to 7f", hex))?;
+                unescaped.push(byte as char);
+            }
+            Some(other) => return Err(format!("unknown escape '\\{}'", other)),
+            None => return Err("trailing '\\'".to_string()),
         }
-    } else {
-        eprintln!("✗ Webhook error: {}", response.status());
     }
-    
-    Ok(())
+    Ok(unescaped)
 }
 
-fn play_sound_file(path: &str, verbose: bool) -> Result<()> {
-    use rodio::{Decoder, OutputStream, Sink};
-    use std::fs::File;
-    use std::io::BufReader;
-    
-    let (_stream, stream_handle) = OutputStream::try_default()?;
-    let sink = Sink::try_new(&stream_handle)?;
+/// Runs `cmd` through the platform shell and returns its trimmed stdout
+fn run_data_cmd(cmd: &str) -> Result<String> {
+    #[cfg(unix)]
+    let output = std::process::Command::new("sh").arg("-c").arg(cmd).output();
+    #[cfg(not(unix))]
+    let output = std::process::Command::new("cmd").arg("/C").arg(cmd).output();
     
-    let file = BufReader::new(File::open(path)?);
-    let source = Decoder::new(file)?;
-    
-    sink.append(source);
-    sink.sleep_until_end();
-    
-    if verbose {
-        println!("✓ Played sound file: {}", path);
+    let output = output.map_err(|e| anyhow::anyhow!("Failed to run '{}': {}", cmd, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        if stderr.is_empty() {
+            return Err(anyhow::anyhow!("'{}' failed ({})", cmd, output.status));
+        }
+        return Err(anyhow::anyhow!("'{}' failed ({}): {}", cmd, output.status, stderr));
     }
-    Ok(())
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-async fn play_sound_url(url: &str, verbose: bool) -> Result<()> {
-    use rodio::{Decoder, OutputStream, Sink};
-    use std::io::Cursor;
+fn parse_webhook_url(s: &str) -> Result<String, String> {
+    let url = reqwest::Url::parse(s).map_err(|e| format!("invalid URL '{}': {}", s, e))?;
+    match url.scheme() {
+        "http" | "https" => Ok(s.to_string()),
+        scheme => Err(format!("unsupported URL scheme '{}', use http or https", scheme)),
+    }
+}
+
+fn parse_cron(s: &str) -> Result<croner::Cron, String> {
+    croner::Cron::new(s)
+        .parse()
+        .map_err(|e| format!("invalid cron expression '{}': {}", s, e))
+}
+
+/// Read the whole of stdin as the message, cut to `max_chars` with an ellipsis
+///
+/// Returns the message and the length it had before truncation.
+fn read_stdin_message(max_chars: usize) -> Result<(String, usize)> {
+    use std::io::Read;
     
-    let client = Client::new();
-    let response = client.get(url).send().await?;
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
     
-    if !response.status().is_success() {
-        return Err(anyhow::anyhow!("Failed to download audio file: {}", response.status()));
+    let message = input.trim();
+    let length = message.chars().count();
+    if length <= max_chars {
+        return Ok((message.to_string(), length));
     }
     
-    let bytes = response.bytes().await?;
-    let cursor = Cursor::new(bytes);
+    let mut truncated: String = message.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    Ok((truncated, length))
+}
+
+fn read_stdin_value() -> Result<f64> {
+    use std::io::Read;
     
-    let (_stream, stream_handle) = OutputStream::try_default()?;
-    let sink = Sink::try_new(&stream_handle)?;
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
     
-    let source = Decoder::new(cursor)?;
-    sink.append(source);
-    sink.sleep_until_end();
+    let token = input
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No value received on stdin"))?;
+    
+    token
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Invalid numeric value on stdin: '{}'", token))
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    general: Option<GeneralConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pushover: Option<PushoverConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    webhook: Option<WebhookConfig>,
+    /// More webhooks, each gets the notification as well as `webhook`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    webhooks: Option<Vec<WebhookConfig>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ifttt: Option<IftttConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    telegram: Option<TelegramConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ntfy: Option<NtfyConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    discord: Option<DiscordConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slack: Option<SlackConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<CommandConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    desktop: Option<DesktopConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sound: Option<SoundConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio: Option<AudioConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    syslog: Option<SyslogConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    routes: Option<Vec<RouteConfig>>,
+    /// Try these providers in order and stop at the first success
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fallback: Option<Vec<Provider>>,
+    /// Dispatch order when sending to all providers, the first one is authoritative
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority_order: Option<Vec<Provider>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Provider {
+    Pushover,
+    Webhook,
+    Ifttt,
+    Telegram,
+    Ntfy,
+    Discord,
+    Slack,
+    Command,
+}
+
+impl Provider {
+    /// Every provider, in the order they are dispatched
+    const ALL: [Provider; 8] = [
+        Provider::Pushover,
+        Provider::Webhook,
+        Provider::Ifttt,
+        Provider::Telegram,
+        Provider::Ntfy,
+        Provider::Discord,
+        Provider::Slack,
+        Provider::Command,
+    ];
+    
+    fn name(self) -> &'static str {
+        match self {
+            Provider::Pushover => "pushover",
+            Provider::Webhook => "webhook",
+            Provider::Ifttt => "ifttt",
+            Provider::Telegram => "telegram",
+            Provider::Ntfy => "ntfy",
+            Provider::Discord => "discord",
+            Provider::Slack => "slack",
+            Provider::Command => "command",
+        }
+    }
+    
+    fn label(self) -> &'static str {
+        match self {
+            Provider::Pushover => "Pushover",
+            Provider::Webhook => "Webhook",
+            Provider::Ifttt => "IFTTT",
+            Provider::Telegram => "Telegram",
+            Provider::Ntfy => "ntfy",
+            Provider::Discord => "Discord",
+            Provider::Slack => "Slack",
+            Provider::Command => "Command",
+        }
+    }
+}
+
+impl Config {
+    fn is_configured(&self, provider: Provider) -> bool {
+        match provider {
+            Provider::Pushover => self.pushover.is_some(),
+            Provider::Webhook => self.all_webhooks().next().is_some(),
+            Provider::Ifttt => self.ifttt.is_some(),
+            Provider::Telegram => self.telegram.is_some(),
+            Provider::Ntfy => self.ntfy.is_some(),
+            Provider::Discord => self.discord.is_some(),
+            Provider::Slack => self.slack.is_some(),
+            Provider::Command => self.command.is_some(),
+        }
+    }
+    
+    /// Providers with a config section, in dispatch order
+    ///
+    /// Providers listed in `priority_order` come first, the rest follow in
+    /// their default order.
+    fn configured_providers(&self) -> Vec<Provider> {
+        let mut providers: Vec<Provider> = Vec::new();
+        let ordered = self.priority_order.iter().flatten().copied();
+        
+        for provider in ordered.chain(Provider::ALL) {
+            if self.is_configured(provider) && !providers.contains(&provider) {
+                providers.push(provider);
+            }
+        }
+        providers
+    }
+    
+    /// `webhook` followed by the `webhooks` list
+    fn all_webhooks(&self) -> impl Iterator<Item = &WebhookConfig> {
+        self.webhook.iter().chain(self.webhooks.iter().flatten())
+    }
+    
+    fn min_priority(&self, provider: Provider) -> Option<i8> {
+        match provider {
+            Provider::Pushover => self.pushover.as_ref().and_then(|c| c.min_priority),
+            // Each webhook checks its own, the provider is skipped only when all would be
+            Provider::Webhook => lowest_limit(self.all_webhooks().map(|c| c.min_priority)),
+            Provider::Ifttt => self.ifttt.as_ref().and_then(|c| c.min_priority),
+            Provider::Telegram => self.telegram.as_ref().and_then(|c| c.min_priority),
+            Provider::Ntfy => self.ntfy.as_ref().and_then(|c| c.min_priority),
+            Provider::Discord => self.discord.as_ref().and_then(|c| c.min_priority),
+            Provider::Slack => self.slack.as_ref().and_then(|c| c.min_priority),
+            Provider::Command => self.command.as_ref().and_then(|c| c.min_priority),
+        }
+    }
+    
+    fn throttle_ms(&self, provider: Provider) -> Option<u64> {
+        match provider {
+            Provider::Pushover => self.pushover.as_ref().and_then(|c| c.throttle_ms),
+            // Webhooks share one throttle, the shortest wins
+            Provider::Webhook => lowest_limit(self.all_webhooks().map(|c| c.throttle_ms)),
+            Provider::Ifttt => self.ifttt.as_ref().and_then(|c| c.throttle_ms),
+            Provider::Telegram => self.telegram.as_ref().and_then(|c| c.throttle_ms),
+            Provider::Ntfy => self.ntfy.as_ref().and_then(|c| c.throttle_ms),
+            Provider::Discord => self.discord.as_ref().and_then(|c| c.throttle_ms),
+            Provider::Slack => self.slack.as_ref().and_then(|c| c.throttle_ms),
+            Provider::Command => self.command.as_ref().and_then(|c| c.throttle_ms),
+        }
+    }
+    
+    /// Per-provider title override, takes precedence over --title
+    fn title(&self, provider: Provider) -> Option<&str> {
+        match provider {
+            Provider::Pushover => self.pushover.as_ref().and_then(|c| c.title.as_deref()),
+            // Each webhook applies its own, see send_notification
+            Provider::Webhook => None,
+            Provider::Ifttt => self.ifttt.as_ref().and_then(|c| c.title.as_deref()),
+            Provider::Telegram => self.telegram.as_ref().and_then(|c| c.title.as_deref()),
+            Provider::Ntfy => self.ntfy.as_ref().and_then(|c| c.title.as_deref()),
+            Provider::Discord => self.discord.as_ref().and_then(|c| c.title.as_deref()),
+            Provider::Slack => self.slack.as_ref().and_then(|c| c.title.as_deref()),
+            Provider::Command => self.command.as_ref().and_then(|c| c.title.as_deref()),
+        }
+    }
+}
+
+/// Lowest of several optional limits, or none if any of them is unset
+fn lowest_limit<T: Ord>(limits: impl Iterator<Item = Option<T>>) -> Option<T> {
+    limits.reduce(|a, b| a.zip(b).map(|(a, b)| a.min(b))).flatten()
+}
+
+/// Time window (local time, "HH:MM") that routes notifications to a set of providers
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RouteConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    from: String,
+    to: String,
+    providers: Vec<RouteTarget>,
+}
+
+/// Where a route sends to: a notification provider, or "desktop" for the local popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+enum RouteTarget {
+    Provider(Provider),
+    Desktop,
+}
+
+impl TryFrom<String> for RouteTarget {
+    type Error = String;
+    
+    fn try_from(name: String) -> Result<Self, String> {
+        if name == "desktop" {
+            return Ok(RouteTarget::Desktop);
+        }
+        <Provider as ValueEnum>::from_str(&name, false)
+            .map(RouteTarget::Provider)
+            .map_err(|_| {
+                let known: Vec<&str> = Provider::ALL.iter().map(|p| p.name()).collect();
+                format!("unknown provider `{}`, expected desktop or one of {}", name, known.join(", "))
+            })
+    }
+}
+
+impl From<RouteTarget> for String {
+    fn from(target: RouteTarget) -> String {
+        match target {
+            RouteTarget::Provider(provider) => provider.name().to_string(),
+            RouteTarget::Desktop => "desktop".to_string(),
+        }
+    }
+}
+
+impl RouteConfig {
+    fn contains(&self, time: NaiveTime) -> Result<bool> {
+        let from = parse_route_time(&self.from)?;
+        let to = parse_route_time(&self.to)?;
+        
+        // Windows like 22:00-07:00 wrap around midnight
+        Ok(if from <= to {
+            time >= from && time < to
+        } else {
+            time >= from || time < to
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct GeneralConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_title: Option<String>,
+    /// Seconds a failed notification stays in the retry queue
+    #[serde(skip_serializing_if = "Option::is_none")]
+    queue_max_age: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PushoverConfig {
+    api_token: String,
+    user_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<String>,
+    /// Notification sound on the receiving device, e.g. "siren" or "none"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sound: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+    /// Send at most one notification per this many ms
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle_ms: Option<u64>,
+    /// Title used instead of --title for this provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct WebhookConfig {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    headers: Option<HashMap<String, String>>,
+    /// PEM client certificate for mutual TLS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_cert: Option<PathBuf>,
+    /// PEM (PKCS#8) private key matching `client_cert`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_key: Option<PathBuf>,
+    /// PEM CA certificate trusted in addition to the system roots
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ca_cert: Option<PathBuf>,
+    /// Skip TLS certificate verification (unsafe, for testing only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    insecure: Option<bool>,
+}
+
+/// IFTTT Webhooks applet, triggered as `event_name`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct IftttConfig {
+    event_name: String,
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// Telegram bot posting to `chat_id` (a user, group or channel)
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct TelegramConfig {
+    bot_token: String,
+    chat_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// ntfy topic on ntfy.sh or a self-hosted `server`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct NtfyConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    server: Option<String>,
+    topic: String,
+    /// Access token for protected topics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// Discord channel webhook (Server Settings → Integrations → Webhooks)
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct DiscordConfig {
+    webhook_url: String,
+    /// Overrides the webhook's default name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// Slack incoming webhook (a Slack app with "Incoming Webhooks" enabled)
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct SlackConfig {
+    webhook_url: String,
+    /// Post somewhere else than the webhook's channel, only legacy webhooks allow it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// Native popup on this machine, shown next to the remote providers
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct DesktopConfig {
+    enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+}
+
+/// External notifier, run directly without a shell
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CommandConfig {
+    /// Program and arguments, with {title}, {message}, {priority} and {severity} filled in
+    run: Vec<String>,
+    /// Also write the message to the command's stdin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stdin: Option<bool>,
+    /// Seconds before the command is killed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct SoundConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    /// 0.0-1.0, applied on top of --gain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume: Option<f32>,
+}
+
+/// Output for the synthesized beep
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct AudioConfig {
+    /// Stable device ID (e.g. ALSA "CARD=PCH,DEV=0"), or a name substring where there are none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_id: Option<String>,
+    /// Loudest the beep may get on a device, by device name substring (0.0-1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_volume: Option<HashMap<String, f32>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct SyslogConfig {
+    /// Facility name such as "user", "daemon" or "local0" (default "user")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    facility: Option<String>,
+    /// Keep printing to stdout/stderr as well
+    #[serde(skip_serializing_if = "Option::is_none")]
+    console: Option<bool>,
+}
+
+fn parse_route_time(s: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .map_err(|_| anyhow::anyhow!("Invalid route time '{}', expected HH:MM", s))
+}
+
+/// First route whose window contains `time`, if any
+fn select_route(routes: &[RouteConfig], time: NaiveTime) -> Result<Option<&RouteConfig>> {
+    for route in routes {
+        if route.contains(time)? {
+            return Ok(Some(route));
+        }
+    }
+    Ok(None)
+}
+
+/// Saved tone parameters loaded with --preset
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Preset {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency: Option<PresetFrequency>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    waveform: Option<Waveform>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fade_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pan: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repeats: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delay: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    semitones: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cents: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    melody: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bpm: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repeat_volumes: Option<Vec<f32>>,
+}
+
+/// A preset's `frequency`, a single tone or a list of them played as a chord
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum PresetFrequency {
+    Single(f32),
+    Chord(Vec<f32>),
+}
+
+impl PresetFrequency {
+    fn into_vec(self) -> Vec<f32> {
+        match self {
+            PresetFrequency::Single(frequency) => vec![frequency],
+            PresetFrequency::Chord(frequencies) => frequencies,
+        }
+    }
+}
+
+/// Path of preset `name`, which has to stay inside the presets directory
+fn get_preset_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(anyhow::anyhow!("Invalid preset name '{}'", name));
+    }
+    let file = format!("{}.yaml", name);
+    
+    if let Some(home) = home_dir() {
+        Ok(home.join(".config").join("beep").join("presets").join(file))
+    } else {
+        Ok(PathBuf::from(file))
+    }
+}
+
+fn load_preset(name: &str) -> Result<Preset> {
+    let path = get_preset_path(name)?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| anyhow::anyhow!("Failed to read preset {}: {}", path.display(), e))?;
+    let preset: Preset = serde_yaml::from_str(&content)?;
+    Ok(preset)
+}
+
+/// Fill in preset values for every tone flag that wasn't given on the command line
+fn apply_preset(args: &mut Args, matches: &ArgMatches, preset: Preset) {
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    
+    if let Some(frequency) = preset.frequency.filter(|_| !from_cli("frequency")) {
+        let frequencies = frequency.into_vec();
+        if !frequencies.is_empty() {
+            args.frequency = frequencies;
+        }
+    }
+    if let Some(length) = preset.length.filter(|_| !from_cli("length")) {
+        args.length = length;
+    }
+    if let Some(waveform) = preset.waveform.filter(|_| !from_cli("waveform")) {
+        args.waveform = waveform;
+    }
+    if let Some(volume) = preset.volume.filter(|_| !from_cli("volume")) {
+        args.volume = volume;
+    }
+    if let Some(fade_ms) = preset.fade_ms.filter(|_| !from_cli("fade_ms")) {
+        args.fade_ms = fade_ms;
+    }
+    if let Some(pan) = preset.pan.filter(|_| !from_cli("pan")) {
+        args.pan = pan.clamp(-1.0, 1.0);
+    }
+    if let Some(repeats) = preset.repeats.filter(|_| !from_cli("repeats")) {
+        args.repeats = repeats;
+    }
+    if let Some(delay) = preset.delay.filter(|_| !from_cli("delay")) {
+        args.delay = delay;
+    }
+    if let Some(bpm) = preset.bpm.filter(|_| !from_cli("bpm")) {
+        args.bpm = bpm;
+    }
+    
+    // Optional flags are only ever set from the command line
+    args.semitones = args.semitones.or(preset.semitones);
+    args.cents = args.cents.or(preset.cents);
+    args.expr = args.expr.take().or(preset.expr);
+    args.melody = args.melody.take().or(preset.melody);
+    if let Some(volumes) = preset.repeat_volumes.filter(|_| !from_cli("repeat_volumes")) {
+        args.repeat_volumes = volumes;
+    }
+}
+
+fn get_config_path(custom_path: Option<PathBuf>) -> PathBuf {
+    if let Some(path) = custom_path {
+        return path;
+    }
+    
+    if let Some(home) = home_dir() {
+        home.join(".config").join("beep.yaml")
+    } else {
+        PathBuf::from("beep.yaml")
+    }
+}
+
+fn load_config(path: &PathBuf) -> Result<Option<Config>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    
+    let content = fs::read_to_string(path)?;
+    
+    // Format follows the extension, anything else is read as YAML
+    let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    let config: Config = match extension.as_deref() {
+        Some("toml") => toml::from_str(&content)?,
+        Some("json") => serde_json::from_str(&content)?,
+        _ => serde_yaml::from_str(&content)?,
+    };
+    Ok(Some(config))
+}
+
+/// Config keys whose values are credentials, or URLs with a credential in them
+const SECRET_KEYS: [&str; 7] = ["api_token", "user_key", "key", "bot_token", "token", "webhook_url", "headers"];
+
+fn mask_secrets(value: &mut serde_yaml::Value) {
+    use serde_yaml::Value;
+    
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, value) in mapping.iter_mut() {
+                match key.as_str() {
+                    // Header values are usually tokens, their names are kept
+                    Some("headers") => {
+                        if let Value::Mapping(headers) = value {
+                            for (_, header) in headers.iter_mut() {
+                                *header = Value::from("********");
+                            }
+                        }
+                    }
+                    Some(key) if SECRET_KEYS.contains(&key) => *value = Value::from("********"),
+                    Some("url") => {
+                        if let Some(url) = value.as_str() {
+                            *value = Value::from(mask_url(url));
+                        }
+                    }
+                    _ => mask_secrets(value),
+                }
+            }
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(mask_secrets),
+        _ => {}
+    }
+}
+
+/// Keep only a URL's scheme and host, webhook paths and queries often carry the token
+fn mask_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}://{}:{}/********", url.scheme(), host, port),
+            (Some(host), None) => format!("{}://{}/********", url.scheme(), host),
+            (None, _) => "********".to_string(),
+        },
+        Err(_) => "********".to_string(),
+    }
+}
+
+/// Serialize the loaded config back to YAML, which loads again as is
+fn dump_config(config: &Config, mask: bool) -> Result<String> {
+    let yaml = serde_yaml::to_string(config)?;
+    if !mask {
+        return Ok(yaml);
+    }
+    
+    // Re-parsed rather than `to_value`, which would widen f32s like 0.8 to 0.800000011920929
+    let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml)?;
+    mask_secrets(&mut value);
+    Ok(serde_yaml::to_string(&value)?)
+}
+
+/// Problems with one provider's settings that loading alone doesn't catch
+fn provider_problems(config: &Config, provider: Provider) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut require = |field: &str, value: &str| {
+        if value.trim().is_empty() {
+            problems.push(format!("{} is empty", field));
+        }
+    };
+    
+    match provider {
+        Provider::Pushover => if let Some(c) = &config.pushover {
+            require("api_token", &c.api_token);
+            require("user_key", &c.user_key);
+        },
+        Provider::Ifttt => if let Some(c) = &config.ifttt {
+            require("event_name", &c.event_name);
+            require("key", &c.key);
+        },
+        Provider::Telegram => if let Some(c) = &config.telegram {
+            require("bot_token", &c.bot_token);
+            require("chat_id", &c.chat_id);
+        },
+        Provider::Ntfy => if let Some(c) = &config.ntfy {
+            require("topic", &c.topic);
+        },
+        Provider::Command => if let Some(c) = &config.command {
+            if c.run.first().is_none_or(|program| program.trim().is_empty()) {
+                problems.push("run needs at least the program to start".to_string());
+            }
+        },
+        Provider::Webhook | Provider::Discord | Provider::Slack => {}
+    }
+    
+    // URLs are checked the same way as --webhook-url
+    let urls: Vec<(&str, &str)> = match provider {
+        Provider::Webhook => config.all_webhooks().map(|c| ("url", c.url.as_str())).collect(),
+        Provider::Ntfy => config.ntfy.as_ref().and_then(|c| c.server.as_deref()).map(|server| ("server", server)).into_iter().collect(),
+        Provider::Discord => config.discord.iter().map(|c| ("webhook_url", c.webhook_url.as_str())).collect(),
+        Provider::Slack => config.slack.iter().map(|c| ("webhook_url", c.webhook_url.as_str())).collect(),
+        _ => Vec::new(),
+    };
+    for (field, url) in urls {
+        if let Err(e) = parse_webhook_url(url) {
+            problems.push(format!("{}: {}", field, e));
+        }
+    }
+    
+    for c in config.all_webhooks().filter(|_| provider == Provider::Webhook) {
+        if let Some(method) = &c.method {
+            if !["GET", "POST", "PUT", "PATCH"].contains(&method.to_uppercase().as_str()) {
+                problems.push(format!("method '{}' isn't one of GET, POST, PUT or PATCH", method));
+            }
+        }
+        if c.client_cert.is_some() != c.client_key.is_some() {
+            problems.push("client_cert and client_key must be set together".to_string());
+        }
+        for path in [&c.client_cert, &c.client_key, &c.ca_cert].into_iter().flatten() {
+            if !path.is_file() {
+                problems.push(format!("{} doesn't exist", path.display()));
+            }
+        }
+    }
+    
+    problems
+}
+
+/// Load the config and report every problem found, per provider
+fn check_config(path: &Path) -> Result<()> {
+    let config = match load_config(&path.to_path_buf()) {
+        Ok(Some(config)) => config,
+        Ok(None) => return Err(anyhow::anyhow!("No configuration at {}", path.display())),
+        Err(e) => return Err(anyhow::anyhow!("{}: {}", path.display(), e)),
+    };
+    
+    println!("Checking {}", path.display());
+    let mut failures = 0;
+    let mut report = |name: &str, problems: Vec<String>| {
+        if problems.is_empty() {
+            println!("✓ {}", name);
+        } else {
+            println!("✗ {}: {}", name, problems.join("; "));
+            failures += 1;
+        }
+    };
+    
+    for provider in config.configured_providers() {
+        report(provider.name(), provider_problems(&config, provider));
+    }
+    
+    // Providers named in routes, fallback or priority_order need a section of their own
+    let unconfigured = |providers: &[Provider]| -> Vec<String> {
+        providers
+            .iter()
+            .filter(|&&p| !config.is_configured(p))
+            .map(|p| format!("{} isn't configured", p.name()))
+            .collect()
+    };
+    if let Some(routes) = &config.routes {
+        let mut problems = Vec::new();
+        for route in routes {
+            for time in [&route.from, &route.to] {
+                if let Err(e) = parse_route_time(time) {
+                    problems.push(e.to_string());
+                }
+            }
+            let providers: Vec<Provider> = route
+                .providers
+                .iter()
+                .filter_map(|&target| match target {
+                    RouteTarget::Provider(provider) => Some(provider),
+                    RouteTarget::Desktop => None,
+                })
+                .collect();
+            problems.extend(unconfigured(&providers));
+        }
+        report("routes", problems);
+    }
+    if let Some(fallback) = &config.fallback {
+        report("fallback", unconfigured(fallback));
+    }
+    if let Some(order) = &config.priority_order {
+        report("priority_order", unconfigured(order));
+    }
+    
+    if failures > 0 {
+        return Err(anyhow::anyhow!("{} section(s) with problems", failures));
+    }
+    Ok(())
+}
+
+const SAMPLE_CONFIG: &str = r#"# Modern Beep Configuration (~/.config/beep.yaml)
+# General settings
+general:
+  default_title: "my-service"  # used when --title isn't passed
+  queue_max_age: 86400  # optional, seconds before an undelivered notification is dropped (default: one day)
+
+# Pushover notifications
+pushover:
+  api_token: "your_api_token_here"
+  user_key: "your_user_key_here"
+  device: "optional_device_name"
+  sound: "siren"  # optional: pushover, bike, bugle, cashregister, classical, cosmic, falling,
+                  # gamelan, incoming, intermission, magic, mechanical, pianobar, siren,
+                  # spacealarm, tugboat, alien, climb, persistent, echo, updown, vibrate, none
+  min_priority: -1  # optional, skip notifications below this priority
+  throttle_ms: 600000  # optional, at most one notification per 10 minutes
+  title: "Build server"  # optional, overrides --title for Pushover
+
+# HTTP Webhook
+webhook:
+  url: "https://example.com/notifications"
+  min_priority: 1  # optional, skip notifications below this priority
+  title: "ci"  # optional, overrides --title for the webhook
+  method: "POST"  # optional, defaults to POST
+  headers:        # optional headers
+    Authorization: "Bearer your_token"
+    Content-Type: "application/json"
+  # client_cert: "/path/to/client.pem"  # optional, for mutual TLS
+  # client_key: "/path/to/client.key"   # optional, PKCS#8 PEM
+  # ca_cert: "/path/to/internal-ca.pem"  # optional, trust a private CA
+  insecure: false  # optional, UNSAFE: skip certificate verification
+
+# More webhooks (optional), each gets the notification too
+webhooks:
+  - url: "https://alerts.internal.example.com/beep"
+  - url: "https://backup.example.com/hook"
+    method: "PUT"
+    min_priority: 1
+
+# IFTTT Webhooks applet (value1 = title, value2 = message, value3 = severity/priority)
+ifttt:
+  event_name: "beep"
+  key: "your_ifttt_key_here"
+  min_priority: 0  # optional, skip notifications below this priority
+
+# Telegram bot (message @BotFather for a token; chat_id of a user, group or channel)
+telegram:
+  bot_token: "123456:your_bot_token_here"
+  chat_id: "123456789"
+  min_priority: -1  # optional, skip notifications below this priority
+
+# ntfy (https://ntfy.sh or self-hosted)
+ntfy:
+  server: "https://ntfy.sh"  # optional, defaults to https://ntfy.sh
+  topic: "your_topic_here"
+  token: "tk_your_access_token"  # optional, for protected topics
+  min_priority: -2  # optional, skip notifications below this priority
+
+# Discord channel webhook
+discord:
+  webhook_url: "https://discord.com/api/webhooks/your_id/your_token"
+  username: "beep"  # optional, overrides the webhook's name
+  min_priority: 0  # optional, skip notifications below this priority
+
+# Slack incoming webhook
+slack:
+  webhook_url: "https://hooks.slack.com/services/T000/B000/your_token"
+  channel: "alerts"  # optional, legacy webhooks only
+  min_priority: 0  # optional, skip notifications below this priority
+
+# External command, {title}, {message}, {priority} and {severity} are filled in
+command:
+  run: ["/usr/local/bin/mynotify", "{title}", "{message}"]
+  stdin: false  # optional, also pipe the message to the command's stdin
+  timeout: 30  # optional, seconds before the command is killed
+  min_priority: 0  # optional, skip notifications below this priority
+
+# Desktop popup on this machine, same as --desktop
+desktop:
+  enabled: true
+  min_priority: 0  # optional, skip notifications below this priority
+
+# Sound file playback
+sound:
+  file: "/path/to/notification.wav"        # local file
+  url: "https://example.com/sound.mp3"     # or remote URL
+  volume: 0.8                              # optional, 0.0-1.0
+
+# Beep output device (optional), pinned by a stable ID such as the
+# ALSA card ID; falls back to matching the device name
+audio:
+  device_id: "CARD=PCH,DEV=0"
+  max_volume:  # optional, loudest the beep may get per device (name substring: 0.0-1.0)
+    "HDMI": 0.2
+
+# Log status and errors to syslog (same as --syslog)
+syslog:
+  facility: "user"  # optional, e.g. daemon or local0
+  console: false    # optional, also print to stdout/stderr
+
+# Time-of-day routing (optional, local time, first match wins)
+routes:
+  - name: "daytime"
+    from: "09:00"
+    to: "18:00"
+    providers: ["webhook"]
+  - name: "nighttime"
+    from: "18:00"
+    to: "09:00"  # windows may wrap around midnight
+    providers: ["pushover", "webhook"]
+
+# Fallback chain (optional): try in order, stop at the first success
+# instead of sending to every provider
+fallback: ["webhook", "pushover"]
+
+# Dispatch order when sending to every provider (optional); the first one
+# is the primary provider whose success counts as delivered
+priority_order: ["pushover", "webhook"]
+"#;
+
+fn print_sample_config() {
+    println!("{}", SAMPLE_CONFIG);
+}
+
+/// Write the sample config to `path`, never over an existing file
+fn init_config(path: &Path) -> Result<()> {
+    use std::io::Write;
+    
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => anyhow::anyhow!("{} already exists, not overwriting it", path.display()),
+            _ => anyhow::anyhow!("Failed to create {}: {}", path.display(), e),
+        })?;
+    file.write_all(SAMPLE_CONFIG.as_bytes())?;
+    Ok(())
+}
+
+type SyslogWriter = syslog::Logger<syslog::LoggerBackend, syslog::Formatter3164>;
+
+/// Sends status and error messages to the console and/or syslog
+struct Logger {
+    syslog: Mutex<Option<SyslogWriter>>,
+    console: AtomicBool,
+}
+
+static LOGGER: Logger = Logger {
+    syslog: Mutex::new(None),
+    console: AtomicBool::new(true),
+};
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        // Records from dependencies aren't meant for the user
+        metadata.target() == module_path!()
+    }
+    
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        
+        if self.console.load(Ordering::Relaxed) {
+            match record.level() {
+                log::Level::Error | log::Level::Warn => eprintln!("{}", record.args()),
+                _ => println!("{}", record.args()),
+            }
+        }
+        
+        if let Some(writer) = self.syslog.lock().unwrap().as_mut() {
+            let message = record.args().to_string();
+            // There is nowhere left to report a failing syslog
+            let _ = match record.level() {
+                log::Level::Error => writer.err(message),
+                log::Level::Warn => writer.warning(message),
+                log::Level::Info => writer.info(message),
+                _ => writer.debug(message),
+            };
+        }
+    }
+    
+    fn flush(&self) {}
+}
+
+/// Switch logging over to syslog if requested on the command line or in the config
+fn init_syslog(requested: bool, config: Option<&SyslogConfig>) -> Result<()> {
+    if !requested && config.is_none() {
+        return Ok(());
+    }
+    
+    let facility_name = config.and_then(|c| c.facility.as_deref()).unwrap_or("user");
+    let facility = facility_name
+        .parse::<syslog::Facility>()
+        .map_err(|_| anyhow::anyhow!("Unknown syslog facility '{}'", facility_name))?;
+    
+    let formatter = syslog::Formatter3164 {
+        facility,
+        hostname: None,
+        process: "beep".to_string(),
+        pid: std::process::id(),
+    };
+    let writer = syslog::unix(formatter)
+        .map_err(|e| anyhow::anyhow!("Failed to connect to syslog: {}", e))?;
+    
+    *LOGGER.syslog.lock().unwrap() = Some(writer);
+    LOGGER.console.store(config.and_then(|c| c.console).unwrap_or(false), Ordering::Relaxed);
+    Ok(())
+}
+
+/// What gets delivered to every notification provider
+#[derive(Clone, Copy)]
+struct Notification<'a> {
+    message: &'a str,
+    title: Option<&'a str>,
+    priority: Option<i8>,
+    severity: Option<Severity>,
+    markdown: bool,
+    /// Pushover only: auto-delete after this many seconds
+    ttl: Option<u32>,
+    /// Pushover only: sound that wins over the configured one
+    sound: Option<&'a str>,
+    /// Pushover only: re-alert interval and lifetime of emergency notifications
+    retry: Option<u32>,
+    expire: Option<u32>,
+    /// Link that silences the local beep, see `AckServer`
+    ack_url: Option<&'a str>,
+    location: Option<Location>,
+}
+
+impl<'a> Notification<'a> {
+    /// Notification carrying the delivery options given on the command line
+    fn from_args(message: &'a str, args: &'a Args) -> Self {
+        Notification {
+            message,
+            title: args.title.as_deref(),
+            priority: args.priority,
+            severity: args.severity,
+            markdown: args.markdown,
+            ttl: args.ttl,
+            sound: args.pushover_sound.as_deref(),
+            retry: args.retry,
+            expire: args.expire,
+            ack_url: None,
+            location: args.location,
+        }
+    }
+    
+    /// Priority on the Pushover -2..2 scale, from --priority or --severity
+    fn effective_priority(&self) -> i8 {
+        self.priority
+            .or(self.severity.map(Severity::pushover_priority))
+            .unwrap_or(0)
+    }
+    
+    /// Message text with the acknowledge link appended, for providers without a URL field
+    fn text_with_ack(&self) -> String {
+        match self.ack_url {
+            Some(url) => format!("{}\n\nAcknowledge: {}", self.message, url),
+            None => self.message.to_string(),
+        }
+    }
+}
+
+/// A provider answered, but rejected the request
+#[derive(Debug)]
+struct ApiError {
+    status: reqwest::StatusCode,
+    detail: Option<String>,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{} ({})", self.status, detail),
+            None => write!(f, "{}", self.status),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Turn an unsuccessful response into an `ApiError`, keeping the service's own explanation
+async fn api_error(response: reqwest::Response) -> anyhow::Error {
+    let status = response.status();
+    
+    // Pushover sends "errors", Telegram "description" and ntfy "error"
+    let detail = response.json::<Value>().await.ok().and_then(|body| {
+        let errors = body["errors"].as_array().map(|errors| {
+            errors.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", ")
+        });
+        errors
+            .filter(|e| !e.is_empty())
+            .or_else(|| body["description"].as_str().map(str::to_string))
+            .or_else(|| body["error"].as_str().map(str::to_string))
+    });
+    ApiError { status, detail }.into()
+}
+
+/// Whether the provider refused the request as malformed (HTTP 400)
+fn is_bad_request(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<ApiError>()
+        .is_some_and(|e| e.status == reqwest::StatusCode::BAD_REQUEST)
+}
+
+/// Describe a failed delivery, telling network trouble apart from rejected requests
+fn describe_delivery_error(e: &anyhow::Error) -> String {
+    if e.downcast_ref::<ApiError>().is_some() {
+        return format!("API error: {}", e);
+    }
+    match e.downcast_ref::<reqwest::Error>() {
+        // An unreadable response body means the service answered, just not as expected
+        Some(cause) if cause.is_decode() => format!("API error: {}", e),
+        Some(cause) if cause.is_timeout() => {
+            let timeout = HTTP_TIMEOUT.get().unwrap_or(&DEFAULT_HTTP_TIMEOUT).as_secs();
+            format!("network error: no response within {} s ({})", timeout, e)
+        }
+        Some(_) => format!("network error: {}", e),
+        None => format!("error: {}", e),
+    }
+}
+
+/// Whether a failed delivery may go through later: no connection, a timeout or a 5xx/429 answer
+///
+/// Rejected requests and setup errors would fail the same way again, so they aren't queued.
+fn is_transient(e: &anyhow::Error) -> bool {
+    if let Some(api) = e.downcast_ref::<ApiError>() {
+        return api.status.is_server_error() || api.status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+    }
+    e.downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request())
+}
+
+/// Timeout of every HTTP request, set from --timeout
+static HTTP_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client builder with the --timeout applied, so a hung server can't stall beep
+fn http_client_builder() -> reqwest::ClientBuilder {
+    let timeout = HTTP_TIMEOUT.get().copied().unwrap_or(DEFAULT_HTTP_TIMEOUT);
+    Client::builder().timeout(timeout)
+}
+
+fn http_client() -> Result<Client> {
+    Ok(http_client_builder().build()?)
+}
+
+/// Client for sound downloads: --timeout bounds connecting and every wait for
+/// data, not the whole transfer, so a large file on a slow link still completes
+fn sound_client() -> Result<Client> {
+    let timeout = HTTP_TIMEOUT.get().copied().unwrap_or(DEFAULT_HTTP_TIMEOUT);
+    Ok(Client::builder().connect_timeout(timeout).build()?)
+}
+
+/// Await a response or the next chunk of one, failing when the server stays silent past --timeout
+async fn with_read_timeout<T>(request: impl std::future::Future<Output = reqwest::Result<T>>) -> Result<T> {
+    let timeout = HTTP_TIMEOUT.get().copied().unwrap_or(DEFAULT_HTTP_TIMEOUT);
+    tokio::time::timeout(timeout, request)
+        .await
+        .map_err(|_| anyhow::anyhow!("no data from the server within {} s", timeout.as_secs()))?
+        .map_err(Into::into)
+}
+
+/// Wait before the first retry, doubled for each one after
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Send `request`, retrying connection errors and 5xx responses with exponential backoff
+///
+/// 4xx responses point at the configuration, so they are returned without retrying.
+async fn send_with_retries(
+    request: reqwest::RequestBuilder,
+    retries: u32,
+    label: &str,
+    verbose: bool,
+) -> Result<reqwest::Response> {
+    let mut attempt = 0;
+    let mut delay = RETRY_BASE_DELAY;
+    
+    loop {
+        let this_try = request
+            .try_clone()
+            .ok_or_else(|| anyhow::anyhow!("request body can't be retried"))?;
+        let last = attempt == retries;
+        
+        let failure = match this_try.send().await {
+            Ok(response) if last || !response.status().is_server_error() => return Ok(response),
+            Ok(response) => response.status().to_string(),
+            Err(e) if !last && (e.is_connect() || e.is_timeout() || e.is_request()) => e.to_string(),
+            Err(e) => return Err(e.into()),
+        };
+        
+        attempt += 1;
+        if verbose {
+            info!("{} attempt {} failed ({}), retrying in {} ms", label, attempt, failure, delay.as_millis());
+        }
+        sleep(delay).await;
+        delay *= 2;
+    }
+}
+
+/// Response of the Pushover messages API, `receipt` is only set for emergency priority
+#[derive(Debug, Deserialize)]
+struct PushoverResponse {
+    receipt: Option<String>,
+}
+
+/// Send via Pushover, returning the receipt of an emergency notification
+async fn send_pushover_notification(
+    config: &PushoverConfig, 
+    notification: &Notification<'_>,
+    retries: u32,
+    verbose: bool
+) -> Result<Option<String>> {
+    let client = http_client()?;
+    let mut params = HashMap::new();
+    
+    params.insert("token", config.api_token.clone());
+    params.insert("user", config.user_key.clone());
+    params.insert("message", notification.message.to_string());
+    
+    if let Some(title) = notification.title {
+        params.insert("title", title.to_string());
+    }
+    
+    if notification.markdown && verbose {
+        info!("Pushover doesn't support markdown, sending as plain text");
+    }
+    
+    if notification.location.is_some() && verbose {
+        info!("Pushover doesn't support locations, skipping --location");
+    }
+    
+    if let Some(device) = &config.device {
+        params.insert("device", device.clone());
+    }
+    
+    if let Some(sound) = notification.sound.or(config.sound.as_deref()) {
+        params.insert("sound", sound.to_string());
+    }
+    
+    if let Some(url) = notification.ack_url {
+        params.insert("url", url.to_string());
+        params.insert("url_title", "Acknowledge".to_string());
+    }
+    
+    // An explicit priority wins over the severity mapping
+    let severity_priority = notification.severity.map(Severity::pushover_priority);
+    if let Some(priority) = notification.priority.or(severity_priority) {
+        params.insert("priority", priority.to_string());
+    }
+    
+    // Pushover rejects emergency notifications without a re-alert schedule
+    if notification.effective_priority() >= 2 {
+        let (Some(retry), Some(expire)) = (notification.retry, notification.expire) else {
+            return Err(anyhow::anyhow!("Emergency priority (-p 2) needs --retry and --expire"));
+        };
+        params.insert("retry", retry.to_string());
+        params.insert("expire", expire.to_string());
+    }
+    
+    // Emergency notifications stay until acknowledged, Pushover ignores ttl for them
+    if let Some(ttl) = notification.ttl {
+        if notification.effective_priority() < 2 {
+            params.insert("ttl", ttl.to_string());
+        } else if verbose {
+            info!("Ignoring --ttl for emergency priority");
+        }
+    }
+    
+    let request = client
+        .post("https://api.pushover.net/1/messages.json")
+        .form(&params);
+    let response = send_with_retries(request, retries, "Pushover", verbose).await?;
+    
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    
+    let receipt = response
+        .json::<PushoverResponse>()
+        .await
+        .ok()
+        .and_then(|r| r.receipt);
+    
+    if verbose {
+        info!("✓ Pushover notification sent");
+    }
+    Ok(receipt)
+}
+
+#[derive(Debug, Deserialize)]
+struct PushoverReceipt {
+    acknowledged: u8,
+    expired: u8,
+}
+
+/// Pushover asks clients not to poll a receipt more often than this
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+async fn fetch_pushover_receipt(client: &Client, config: &PushoverConfig, receipt: &str) -> Result<PushoverReceipt> {
+    let response = client
+        .get(format!("https://api.pushover.net/1/receipts/{}.json", receipt))
+        .query(&[("token", config.api_token.as_str())])
+        .send()
+        .await?;
+    
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    Ok(response.json().await?)
+}
+
+fn local_hostname() -> String {
+    hostname::get()
+        .map(|h| h.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// `{token}` placeholder, compiled on first use and shared by every alert
+fn token_pattern() -> &'static regex::Regex {
+    static TOKEN: OnceLock<regex::Regex> = OnceLock::new();
+    TOKEN.get_or_init(|| regex::Regex::new(r"\{(\w+)\}").expect("valid token pattern"))
+}
+
+/// Fill in {hostname}, {timestamp}, {date} and {time} in a -D message
+///
+/// Unknown tokens are left as they are, so JSON and other braces pass through.
+fn render_message(template: &str, exit_code: Option<i32>) -> String {
+    let now = Local::now();
+    
+    token_pattern()
+        .replace_all(template, |caps: &regex::Captures| match &caps[1] {
+            "hostname" => local_hostname(),
+            "timestamp" => now.to_rfc3339(),
+            "date" => now.format("%Y-%m-%d").to_string(),
+            "time" => now.format("%H:%M:%S").to_string(),
+            "exit_code" => match exit_code {
+                Some(code) => code.to_string(),
+                None => caps[0].to_string(),
+            },
+            _ => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+fn build_webhook_meta(args: &Args, notification: &Notification) -> Value {
+    let mut meta = json!({
+        "hostname": local_hostname(),
+        "timestamp": Local::now().to_rfc3339(),
+        "title": notification.title,
+        "priority": notification.priority,
+        "severity": notification.severity,
+        "format": if notification.markdown { "markdown" } else { "plain" },
+        "ack_url": notification.ack_url,
+        "location": notification.location,
+        "beep": {
+            "frequency": frequency_json(&args.frequency),
+            "length": args.length,
+            "repeats": args.repeats,
+            "delay": args.delay,
+        },
+    });
+    if args.include_tone_info {
+        meta["tone"] = tone_info(args);
+    }
+    meta
+}
+
+/// Parameters of the beep that fired, for --include-tone-info
+fn tone_info(args: &Args) -> Value {
+    let mut info = json!({ "waveform": waveform_name(args.waveform) });
+    match (&args.melody, &args.expr) {
+        (Some(melody), _) => info["melody"] = json!(melody),
+        (None, Some(expr)) => {
+            info["expr"] = json!(expr);
+            info["length"] = json!(args.length);
+        }
+        (None, None) => {
+            info["frequency"] = frequency_json(&args.frequency);
+            info["length"] = json!(args.length);
+        }
+    }
+    info
+}
+
+/// A single frequency as a number, a chord as a list of them
+fn frequency_json(frequencies: &[f32]) -> Value {
+    match frequencies {
+        [frequency] => json!(frequency),
+        chord => json!(chord),
+    }
+}
+
+/// Frequencies joined with "+", rounded to 0.01 Hz
+fn format_frequencies(frequencies: &[f32]) -> String {
+    frequencies
+        .iter()
+        .map(|f| ((f * 100.0).round() / 100.0).to_string())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Message with the tone info added: a `tone` field in JSON objects, a trailing line otherwise
+///
+/// With --include-meta JSON payloads carry it in the metadata instead.
+fn add_tone_info(message: &str, args: &Args) -> String {
+    if let Ok(Value::Object(mut object)) = serde_json::from_str::<Value>(message) {
+        if args.include_meta {
+            return message.to_string();
+        }
+        object.insert("tone".to_string(), tone_info(args));
+        return Value::Object(object).to_string();
+    }
+    
+    let sound = match (&args.melody, &args.expr) {
+        (Some(melody), _) => format!("melody \"{}\"", melody),
+        (None, Some(expr)) => format!("expr \"{}\", {} ms", expr, args.length),
+        (None, None) => format!("{} Hz, {} ms", format_frequencies(&args.frequency), args.length),
+    };
+    format!("{}\n\nTone: {}, {}", message, sound, waveform_name(args.waveform))
+}
+
+fn build_webhook_client(config: &WebhookConfig, insecure: bool) -> Result<Client> {
+    let mut builder = http_client_builder();
+    
+    if insecure || config.insecure.unwrap_or(false) {
+        warn!("⚠ WARNING: TLS certificate verification is disabled for {}, anyone on the network can intercept this webhook", config.url);
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    
+    match (&config.client_cert, &config.client_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = fs::read(cert_path)
+                .map_err(|e| anyhow::anyhow!("Failed to read client_cert {}: {}", cert_path.display(), e))?;
+            let key = fs::read(key_path)
+                .map_err(|e| anyhow::anyhow!("Failed to read client_key {}: {}", key_path.display(), e))?;
+            builder = builder.identity(reqwest::Identity::from_pkcs8_pem(&cert, &key)?);
+        }
+        (None, None) => {}
+        _ => return Err(anyhow::anyhow!("client_cert and client_key must be set together")),
+    }
+    
+    if let Some(ca_path) = &config.ca_cert {
+        let ca = fs::read(ca_path)
+            .map_err(|e| anyhow::anyhow!("Failed to read ca_cert {}: {}", ca_path.display(), e))?;
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&ca)?);
+    }
+    
+    Ok(builder.build()?)
+}
+
+async fn send_webhook_notification(
+    config: &WebhookConfig, 
+    notification: &Notification<'_>,
+    meta: Option<&Value>,
+    insecure: bool,
+    retries: u32,
+    verbose: bool
+) -> Result<()> {
+    let data = notification.message;
+    let client = build_webhook_client(config, insecure)?;
+    let method = config.method.as_deref().unwrap_or("POST");
+    
+    let mut request = match method.to_uppercase().as_str() {
+        "GET" => client.get(&config.url),
+        "PUT" => client.put(&config.url),
+        "PATCH" => client.patch(&config.url),
+        _ => client.post(&config.url),
+    };
+    
+    // Próbuj sparsować jako JSON, jeśli się nie uda - wyślij jako tekst
+    if let Ok(mut json_value) = serde_json::from_str::<Value>(data) {
+        if let Some(meta) = meta {
+            request = request.json(&json!({ "meta": meta, "message": json_value }));
+        } else {
+            // Without metadata the location goes straight into the payload
+            if let (Some(location), Some(object)) = (notification.location, json_value.as_object_mut()) {
+                object.insert("location".to_string(), json!(location));
+            }
+            request = request.json(&json_value);
+        }
+    } else {
+        request = request.body(notification.text_with_ack());
+    }
+    
+    // Dodaj niestandardowe nagłówki
+    if let Some(headers) = &config.headers {
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
+    }
+    
+    let response = send_with_retries(request, retries, "Webhook", verbose).await?;
+    
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    
+    if verbose {
+        info!("✓ Webhook sent to {}", config.url);
+    }
+    Ok(())
+}
+
+/// Escape text for Telegram's HTML parse mode
+fn telegram_html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Escape text for Telegram's legacy Markdown parse mode, where only `_`, `*`, `` ` `` and `[` are reserved
+fn telegram_markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "_*`[".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// POST a Bot API method, surfacing Telegram's error description on failure
+async fn telegram_request(client: &Client, config: &TelegramConfig, method: &str, payload: &Value) -> Result<()> {
+    let url = format!("https://api.telegram.org/bot{}/{}", config.bot_token, method);
+    
+    // The token is part of the URL, keep it out of error messages
+    let response = client
+        .post(&url)
+        .json(payload)
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?;
+    
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    Ok(())
+}
+
+async fn send_telegram_notification(
+    config: &TelegramConfig,
+    notification: &Notification<'_>,
+    verbose: bool
+) -> Result<()> {
+    let client = http_client()?;
+    
+    // --markdown passes the message through as legacy Markdown, which leaves plain
+    // punctuation alone, otherwise it is escaped into HTML
+    let (text, parse_mode) = if notification.markdown {
+        let mut text = String::new();
+        if let Some(title) = notification.title {
+            text.push_str(&format!("*{}*\n", telegram_markdown_escape(title)));
+        }
+        text.push_str(notification.message);
+        if let Some(url) = notification.ack_url {
+            text.push_str(&format!("\n\n[Acknowledge]({})", url));
+        }
+        (text, "Markdown")
+    } else {
+        let mut text = String::new();
+        if let Some(title) = notification.title {
+            text.push_str(&format!("<b>{}</b>\n", telegram_html_escape(title)));
+        }
+        text.push_str(&telegram_html_escape(notification.message));
+        if let Some(url) = notification.ack_url {
+            text.push_str(&format!("\n\n<a href=\"{}\">Acknowledge</a>", telegram_html_escape(url)));
+        }
+        (text, "HTML")
+    };
+    
+    let mut payload = json!({
+        "chat_id": config.chat_id,
+        "text": text,
+        "parse_mode": parse_mode,
+    });
+    // Low priorities arrive without a sound, like Pushover's quiet notifications
+    if notification.effective_priority() < 0 {
+        payload["disable_notification"] = json!(true);
+    }
+    match telegram_request(&client, config, "sendMessage", &payload).await {
+        // Unbalanced markup is rejected outright, deliver the text unformatted instead
+        Err(e) if notification.markdown && is_bad_request(&e) => {
+            if verbose {
+                warn!("Telegram rejected the Markdown ({}), sending as plain text", e);
+            }
+            let mut text = String::new();
+            if let Some(title) = notification.title {
+                text.push_str(&format!("{}\n", title));
+            }
+            text.push_str(&notification.text_with_ack());
+            payload["text"] = json!(text);
+            if let Some(fields) = payload.as_object_mut() {
+                fields.remove("parse_mode");
+            }
+            telegram_request(&client, config, "sendMessage", &payload).await?;
+        }
+        result => result?,
+    }
+    
+    // Locations are a message of their own, shown as a map pin under the text
+    if let Some(location) = notification.location {
+        telegram_request(&client, config, "sendLocation", &json!({
+            "chat_id": config.chat_id,
+            "latitude": location.lat,
+            "longitude": location.lon,
+            "disable_notification": true,
+        }))
+        .await?;
+    }
+    
+    if verbose {
+        info!("✓ Telegram message sent to chat {}", config.chat_id);
+    }
+    Ok(())
+}
+
+/// Default ntfy server when `ntfy.server` isn't set
+const NTFY_SERVER: &str = "https://ntfy.sh";
+
+async fn send_ntfy_notification(
+    config: &NtfyConfig,
+    notification: &Notification<'_>,
+    verbose: bool
+) -> Result<()> {
+    let client = http_client()?;
+    let server = config.server.as_deref().unwrap_or(NTFY_SERVER).trim_end_matches('/');
+    let url = format!("{}/{}", server, config.topic);
+    
+    // ntfy priorities run 1 (min) to 5 (max), Pushover's -2..2 shifted up
+    let priority = (notification.effective_priority().clamp(-2, 2) + 3).to_string();
+    
+    let mut request = client
+        .put(&url)
+        .header("Priority", priority)
+        .body(notification.message.to_string());
+    
+    // Headers are ASCII-only, ntfy takes the same parameters in the query string
+    match notification.title {
+        Some(title) if title.is_ascii() => request = request.header("Title", title),
+        Some(title) => request = request.query(&[("title", title)]),
+        None => {}
+    }
+    if let Some(token) = &config.token {
+        request = request.bearer_auth(token);
+    }
+    if notification.markdown {
+        request = request.header("Markdown", "yes");
+    }
+    if let Some(url) = notification.ack_url {
+        request = request.header("Actions", format!("view, Acknowledge, {}, clear=true", url));
+    }
+    // Tapping the notification opens the location in a maps app
+    if let Some(location) = notification.location {
+        request = request.header("Click", format!("geo:{},{}", location.lat, location.lon));
+    }
+    
+    let response = request.send().await?;
+    
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    
+    if verbose {
+        info!("✓ ntfy notification sent to {}", url);
+    }
+    Ok(())
+}
+
+/// Escape Discord markdown, so plain text shows up as written
+fn discord_markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\*_~`|>#-[]()".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+async fn send_discord_notification(
+    config: &DiscordConfig,
+    notification: &Notification<'_>,
+    verbose: bool
+) -> Result<()> {
+    let client = http_client()?;
+    
+    let mut text = if notification.markdown {
+        notification.message.to_string()
+    } else {
+        discord_markdown_escape(notification.message)
+    };
+    if let Some(url) = notification.ack_url {
+        text.push_str(&format!("\n\n[Acknowledge]({})", url));
+    }
+    
+    if notification.location.is_some() && verbose {
+        info!("Discord webhooks have no location field, skipping --location");
+    }
+    
+    // Never ping @everyone or roles because a log line happened to contain a mention
+    let mut payload = json!({ "allowed_mentions": { "parse": [] } });
+    
+    // A title turns the message into an embed, colored by severity
+    match notification.title {
+        Some(title) => {
+            let mut embed = json!({ "title": title, "description": text });
+            if let Some(severity) = notification.severity {
+                embed["color"] = json!(severity.discord_color());
+            }
+            payload["embeds"] = json!([embed]);
+        }
+        None => payload["content"] = json!(text),
+    }
+    if let Some(username) = &config.username {
+        payload["username"] = json!(username);
+    }
+    // Low priority messages arrive without a push or sound (SUPPRESS_NOTIFICATIONS)
+    if notification.effective_priority() < 0 {
+        payload["flags"] = json!(1 << 12);
+    }
+    
+    let response = client
+        .post(&config.webhook_url)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?;
+    
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    
+    if verbose {
+        info!("✓ Discord notification sent");
+    }
+    Ok(())
+}
+
+/// Escape the three characters Slack reserves for links and mentions
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+async fn send_slack_notification(
+    config: &SlackConfig,
+    notification: &Notification<'_>,
+    verbose: bool
+) -> Result<()> {
+    let client = http_client()?;
+    
+    // Without --markdown the text is shown as typed, so the title goes unstyled too
+    let mut text = slack_escape(notification.message);
+    if let Some(title) = notification.title {
+        text = if notification.markdown {
+            format!("*{}*\n{}", slack_escape(title), text)
+        } else {
+            format!("{}\n{}", slack_escape(title), text)
+        };
+    }
+    if let Some(url) = notification.ack_url {
+        text.push_str(&format!("\n\n<{}|Acknowledge>", url));
+    }
+    
+    if notification.location.is_some() && verbose {
+        info!("Slack webhooks have no location field, skipping --location");
+    }
+    
+    let mut payload = json!({ "text": text, "mrkdwn": notification.markdown });
+    if let Some(channel) = &config.channel {
+        payload["channel"] = json!(channel);
+    }
+    
+    // The URL is the credential, keep it out of error messages
+    let response = client
+        .post(&config.webhook_url)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?;
+    
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    
+    if verbose {
+        info!("✓ Slack notification sent");
+    }
+    Ok(())
+}
+
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 30;
+
+async fn send_command_notification(
+    config: &CommandConfig,
+    notification: &Notification<'_>,
+    verbose: bool
+) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+    
+    let (program, args) = config
+        .run
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("command.run is empty"))?;
+    
+    let message = notification.text_with_ack();
+    let priority = notification.effective_priority().to_string();
+    let severity = notification
+        .severity
+        .and_then(|s| s.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default();
+    
+    // One pass, so a {token} inside the message itself is never expanded
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| {
+            token_pattern()
+                .replace_all(arg, |caps: &regex::Captures| match &caps[1] {
+                    "title" => notification.title.unwrap_or_default().to_string(),
+                    "message" => message.clone(),
+                    "priority" => priority.clone(),
+                    "severity" => severity.clone(),
+                    _ => caps[0].to_string(),
+                })
+                .into_owned()
+        })
+        .collect();
+    
+    if notification.location.is_some() && verbose {
+        info!("Commands have no location field, skipping --location");
+    }
+    
+    let pipe_stdin = config.stdin.unwrap_or(false);
+    let mut child = tokio::process::Command::new(program)
+        .args(&args)
+        .stdin(if pipe_stdin { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to run '{}': {}", program, e))?;
+    
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that exits without reading stdin is fine
+        match stdin.write_all(message.as_bytes()).await {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+            _ => {}
+        }
+    }
+    
+    let timeout = Duration::from_secs(config.timeout.unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS));
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("'{}' timed out after {}s", program, timeout.as_secs()))??;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        if stderr.is_empty() {
+            return Err(anyhow::anyhow!("'{}' failed ({})", program, output.status));
+        }
+        return Err(anyhow::anyhow!("'{}' failed ({}): {}", program, output.status, stderr));
+    }
+    
+    if verbose {
+        info!("✓ Command {} ran", program);
+    }
+    Ok(())
+}
+
+async fn send_ifttt_notification(
+    config: &IftttConfig,
+    notification: &Notification<'_>,
+    verbose: bool
+) -> Result<()> {
+    let client = http_client()?;
+    let url = format!(
+        "https://maker.ifttt.com/trigger/{}/with/key/{}",
+        config.event_name, config.key
+    );
+    
+    // value3 carries the severity when given, otherwise the numeric priority
+    let extra = match notification.severity {
+        Some(severity) => severity.to_possible_value().map(|v| v.get_name().to_string()),
+        None => notification.priority.map(|p| p.to_string()),
+    };
+    
+    if notification.location.is_some() && verbose {
+        info!("IFTTT has no location field, skipping --location");
+    }
+    
+    // The key is part of the URL, keep it out of error messages
+    let response = client
+        .post(&url)
+        .json(&json!({
+            "value1": notification.title.unwrap_or(""),
+            "value2": notification.text_with_ack(),
+            "value3": extra.unwrap_or_default(),
+        }))
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?;
+    
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    
+    if verbose {
+        info!("✓ IFTTT event '{}' triggered", config.event_name);
+    }
+    Ok(())
+}
+
+/// Fallback chain for a sound that fails to play: the beep, then the terminal bell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SoundFallback {
+    /// Play the beep, or ring the terminal bell if that fails too
+    Beep,
+    /// Only ring the terminal bell
+    Bell,
+    /// Stay silent, also when the beep fails
+    None,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum BuiltinSound {
+    Chime,
+    Alarm,
+    Ding,
+}
+
+impl BuiltinSound {
+    fn name(self) -> &'static str {
+        match self {
+            BuiltinSound::Chime => "chime",
+            BuiltinSound::Alarm => "alarm",
+            BuiltinSound::Ding => "ding",
+        }
+    }
+    
+    fn bytes(self) -> &'static [u8] {
+        match self {
+            BuiltinSound::Chime => include_bytes!("../assets/sounds/chime.wav"),
+            BuiltinSound::Alarm => include_bytes!("../assets/sounds/alarm.wav"),
+            BuiltinSound::Ding => include_bytes!("../assets/sounds/ding.wav"),
+        }
+    }
+}
+
+/// Output device whose name contains `name` (case-insensitive)
+fn find_output_device(host: &cpal::Host, name: &str) -> Result<cpal::Device> {
+    let wanted = name.to_lowercase();
+    let mut available = Vec::new();
+    
+    for device in host.output_devices()? {
+        let device_name = device.name().unwrap_or_default();
+        if device_name.to_lowercase().contains(&wanted) {
+            return Ok(device);
+        }
+        available.push(device_name);
+    }
+    
+    Err(anyhow::anyhow!(
+        "No output device matching '{}'. Available: {}",
+        name,
+        available.join(", ")
+    ))
+}
+
+/// Open a rodio output on the named device, or the default one
+fn open_sound_output(device: Option<&str>) -> Result<(rodio::OutputStream, rodio::OutputStreamHandle)> {
+    use rodio::OutputStream;
+    
+    match device {
+        Some(name) => Ok(OutputStream::try_from_device(&find_output_device(&cpal::default_host(), name)?)?),
+        None => Ok(OutputStream::try_default()?),
+    }
+}
+
+/// Block until `sink` has played everything, with --sound-progress printing the time to stderr
+///
+/// rodio can't report the playback position, so progress is timed from the start.
+fn wait_for_sink(sink: &rodio::Sink, total: Option<Duration>, progress: bool) {
+    if !progress {
+        sink.sleep_until_end();
+        return;
+    }
+    
+    let started = Instant::now();
+    while !sink.empty() {
+        let elapsed = started.elapsed().as_secs_f32();
+        match total.map(|t| t.as_secs_f32()).filter(|&t| t > 0.0) {
+            Some(total) => eprint!(
+                "\r🔊 {:.1}s / {:.1}s ({:>3.0}%)",
+                elapsed.min(total),
+                total,
+                (elapsed / total).min(1.0) * 100.0
+            ),
+            None => eprint!("\r🔊 {:.1}s", elapsed),
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    eprintln!();
+}
+
+/// Queue `source` `repeats` times with `delay` of silence between plays, returning the total length
+///
+/// The source is buffered, so later plays replay the decoded samples instead of decoding
+/// (or downloading) it again.
+fn append_repeated<S>(sink: &rodio::Sink, source: S, repeats: u32, delay: Duration) -> Option<Duration>
+where
+    S: rodio::Source + Send + Sync + 'static,
+    S::Item: rodio::Sample + Send + Sync,
+    f32: FromSample<S::Item>,
+{
+    use rodio::Source;
+    
+    let repeats = repeats.max(1);
+    let source = source.buffered();
+    let total = source
+        .total_duration()
+        .map(|length| length * repeats + delay * (repeats - 1));
+    
+    for i in 0..repeats {
+        let pause = if i == 0 { Duration::ZERO } else { delay };
+        sink.append(source.clone().delay(pause));
+    }
+    total
+}
+
+fn play_builtin_sound(
+    sound: BuiltinSound,
+    device: Option<&str>,
+    gain: f32,
+    repeats: u32,
+    delay: Duration,
+    progress: bool,
+    verbose: bool,
+) -> Result<()> {
+    use rodio::{Decoder, Sink, Source};
+    use std::io::Cursor;
+    
+    let (_stream, stream_handle) = open_sound_output(device)?;
+    let sink = Sink::try_new(&stream_handle)?;
+    
+    let source = Decoder::new(Cursor::new(sound.bytes()))?;
+    let total = append_repeated(&sink, source.amplify(gain), repeats, delay);
+    wait_for_sink(&sink, total, progress);
+    
+    if verbose {
+        info!("✓ Played built-in sound: {}", sound.name());
+    }
+    Ok(())
+}
+
+/// Open and decode a local sound file
+fn decode_sound_file(path: &str) -> Result<rodio::Decoder<std::io::BufReader<fs::File>>> {
+    let file = std::io::BufReader::new(fs::File::open(path)?);
+    Ok(rodio::Decoder::new(file)?)
+}
+
+fn play_sound_file(
+    path: &str,
+    device: Option<&str>,
+    gain: f32,
+    repeats: u32,
+    delay: Duration,
+    progress: bool,
+    verbose: bool,
+) -> Result<()> {
+    use rodio::{Sink, Source};
+    
+    let (_stream, stream_handle) = open_sound_output(device)?;
+    let sink = Sink::try_new(&stream_handle)?;
+    
+    let source = decode_sound_file(path)?;
+    let total = append_repeated(&sink, source.amplify(gain), repeats, delay);
+    wait_for_sink(&sink, total, progress);
+    
+    if verbose {
+        info!("✓ Played sound file: {}", path);
+    }
+    Ok(())
+}
+
+/// Loop a sound file until the Pushover `receipt` is acknowledged, returning whether it was
+///
+/// Stops early without acknowledgement when the receipt expires.
+async fn play_sound_file_until_ack(
+    path: &str,
+    device: Option<&str>,
+    gain: f32,
+    config: &PushoverConfig,
+    receipt: &str,
+    verbose: bool,
+) -> Result<bool> {
+    use rodio::{Sink, Source};
+    
+    let (stop, stop_rx) = std::sync::mpsc::channel::<()>();
+    let file_path = path.to_string();
+    let device = device.map(str::to_string);
+    let playback = tokio::task::spawn_blocking(move || -> Result<()> {
+        let (_stream, stream_handle) = open_sound_output(device.as_deref())?;
+        let sink = Sink::try_new(&stream_handle)?;
+        
+        let source = decode_sound_file(&file_path)?;
+        sink.append(source.amplify(gain).repeat_infinite());
+        
+        // Keeps looping until told to stop (or the sender goes away)
+        let _ = stop_rx.recv();
+        sink.stop();
+        Ok(())
+    });
+    
+    if verbose {
+        info!("🔁 Repeating {} until acknowledged", path);
+    }
+    
+    let client = http_client()?;
+    let acknowledged = loop {
+        sleep(RECEIPT_POLL_INTERVAL).await;
+        
+        // Playback failed to start, the error is reported below
+        if playback.is_finished() {
+            break false;
+        }
+        
+        match fetch_pushover_receipt(&client, config, receipt).await {
+            Ok(status) if status.acknowledged != 0 => break true,
+            Ok(status) if status.expired != 0 => {
+                info!("Pushover emergency notification expired without acknowledgement");
+                break false;
+            }
+            Ok(_) => {}
+            Err(e) => error!("Error polling Pushover receipt: {}", e),
+        }
+    };
+    
+    let _ = stop.send(());
+    playback.await??;
+    Ok(acknowledged)
+}
+
+/// How often an interrupted sound download is resumed before giving up
+const MAX_DOWNLOAD_RESUMES: u32 = 3;
+
+/// Bytes of a remote sound shared between the download task and the decoder
+struct SoundBuffer {
+    state: Mutex<SoundBufferState>,
+    ready: Condvar,
+}
+
+struct SoundBufferState {
+    bytes: Vec<u8>,
+    total_len: Option<u64>,
+    finished: bool,
+    error: Option<String>,
+}
+
+impl SoundBuffer {
+    fn new(total_len: Option<u64>) -> Self {
+        SoundBuffer {
+            state: Mutex::new(SoundBufferState {
+                bytes: Vec::new(),
+                total_len,
+                finished: false,
+                error: None,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+    
+    fn len(&self) -> u64 {
+        self.state.lock().unwrap().bytes.len() as u64
+    }
+    
+    fn push(&self, chunk: &[u8]) {
+        self.state.lock().unwrap().bytes.extend_from_slice(chunk);
+        self.ready.notify_all();
+    }
+    
+    fn finish(&self, error: Option<String>) {
+        let mut state = self.state.lock().unwrap();
+        state.finished = true;
+        state.error = error;
+        self.ready.notify_all();
+    }
+}
+
+/// Blocking reader over a `SoundBuffer` that waits for bytes as they arrive
+struct SoundStreamReader {
+    buffer: Arc<SoundBuffer>,
+    pos: u64,
+}
+
+impl std::io::Read for SoundStreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut state = self.buffer.state.lock().unwrap();
+        while state.bytes.len() as u64 <= self.pos && !state.finished {
+            state = self.buffer.ready.wait(state).unwrap();
+        }
+        
+        let available = state.bytes.len() as u64;
+        if self.pos >= available {
+            return match &state.error {
+                Some(e) => Err(std::io::Error::other(e.clone())),
+                None => Ok(0),
+            };
+        }
+        
+        let start = self.pos as usize;
+        let count = buf.len().min(state.bytes.len() - start);
+        buf[..count].copy_from_slice(&state.bytes[start..start + count]);
+        self.pos += count as u64;
+        Ok(count)
+    }
+}
+
+impl std::io::Seek for SoundStreamReader {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        use std::io::SeekFrom;
+        
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::Current(offset) => self.pos as i64 + offset,
+            SeekFrom::End(offset) => {
+                // Without a Content-Length we only know the end once the download is done
+                let mut state = self.buffer.state.lock().unwrap();
+                while state.total_len.is_none() && !state.finished {
+                    state = self.buffer.ready.wait(state).unwrap();
+                }
+                let end = state.total_len.unwrap_or(state.bytes.len() as u64);
+                end as i64 + offset
+            }
+        };
+        
+        if target < 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek before start of stream",
+            ));
+        }
+        
+        self.pos = target as u64;
+        Ok(self.pos)
+    }
+}
+
+async fn download_sound(
+    client: Client,
+    url: String,
+    mut response: reqwest::Response,
+    buffer: Arc<SoundBuffer>,
+    verbose: bool,
+) {
+    let mut resumes = 0;
+    
+    loop {
+        let error = match read_sound_chunks(&mut response, &buffer).await {
+            Ok(()) => {
+                buffer.finish(None);
+                return;
+            }
+            Err(e) => e,
+        };
+        
+        if resumes >= MAX_DOWNLOAD_RESUMES {
+            buffer.finish(Some(format!("Sound download failed: {}", error)));
+            return;
+        }
+        resumes += 1;
+        
+        let offset = buffer.len();
+        if verbose {
+            info!("↻ Sound download interrupted at {} bytes, resuming ({})", offset, error);
+        }
+        sleep(Duration::from_millis(500)).await;
+        
+        // Ask only for the missing tail of the file
+        let resumed = with_read_timeout(
+            client
+                .get(&url)
+                .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+                .send(),
+        )
+        .await;
+        
+        response = match resumed {
+            Ok(resumed) if resumed.status() == reqwest::StatusCode::PARTIAL_CONTENT => resumed,
+            Ok(resumed) if resumed.status().is_success() => {
+                // Server ignored the range request, drop what we already have
+                match skip_sound_bytes(resumed, offset).await {
+                    Ok(resumed) => resumed,
+                    Err(e) => {
+                        buffer.finish(Some(format!("Sound download failed: {}", e)));
+                        return;
+                    }
+                }
+            }
+            Ok(resumed) => {
+                buffer.finish(Some(format!("Failed to resume audio download: {}", resumed.status())));
+                return;
+            }
+            Err(e) => {
+                if verbose {
+                    info!("↻ Resume request failed: {}", e);
+                }
+                continue;
+            }
+        };
+    }
+}
+
+async fn read_sound_chunks(response: &mut reqwest::Response, buffer: &SoundBuffer) -> Result<()> {
+    while let Some(chunk) = with_read_timeout(response.chunk()).await? {
+        buffer.push(&chunk);
+    }
+    Ok(())
+}
+
+async fn skip_sound_bytes(mut response: reqwest::Response, mut skip: u64) -> Result<reqwest::Response> {
+    while skip > 0 {
+        let chunk = with_read_timeout(response.chunk())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Resumed download ended early"))?;
+        skip = skip.saturating_sub(chunk.len() as u64);
+    }
+    Ok(response)
+}
+
+/// Describe a decoded sound as "2 ch, 44100 Hz, 1.25 s", after making sure it yields a sample
+fn describe_sound<S>(mut source: S) -> Result<String>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    let channels = source.channels();
+    let sample_rate = source.sample_rate();
+    let duration = source.total_duration();
+    
+    if source.next().is_none() {
+        return Err(anyhow::anyhow!("no audio frames"));
+    }
+    
+    // Most decoders can't tell the length up front, so count the rest
+    let seconds = match duration {
+        Some(duration) => duration.as_secs_f32(),
+        None => (source.count() + 1) as f32 / (sample_rate as f32 * channels as f32),
+    };
+    Ok(format!("{} ch, {} Hz, {:.2} s", channels, sample_rate, seconds))
+}
+
+/// Decode the configured sound file and URL without playing them, for --check-sound
+async fn check_sound(config: Option<&SoundConfig>) -> Result<()> {
+    let file = config.and_then(|c| c.file.as_deref());
+    let url = config.and_then(|c| c.url.as_deref());
+    if file.is_none() && url.is_none() {
+        return Err(anyhow::anyhow!("No sound.file or sound.url configured"));
+    }
+    
+    let mut failed = false;
+    
+    if let Some(path) = file {
+        match decode_sound_file(path).and_then(describe_sound) {
+            Ok(description) => info!("✓ {}: {}", path, description),
+            Err(e) => {
+                error!("✗ {}: {}", path, e);
+                failed = true;
+            }
+        }
+    }
+    
+    if let Some(url) = url {
+        let decoded = async {
+            let mut response = with_read_timeout(sound_client()?.get(url).send()).await?.error_for_status()?;
+            let mut bytes = Vec::new();
+            while let Some(chunk) = with_read_timeout(response.chunk()).await? {
+                bytes.extend_from_slice(&chunk);
+            }
+            describe_sound(rodio::Decoder::new(std::io::Cursor::new(bytes))?)
+        };
+        match decoded.await {
+            Ok(description) => info!("✓ {}: {}", url, description),
+            Err(e) => {
+                error!("✗ {}: {}", url, e);
+                failed = true;
+            }
+        }
+    }
+    
+    if failed {
+        return Err(anyhow::anyhow!("Sound check failed"));
+    }
+    Ok(())
+}
+
+async fn play_sound_url(
+    url: &str,
+    device: Option<&str>,
+    gain: f32,
+    repeats: u32,
+    delay: Duration,
+    progress: bool,
+    verbose: bool,
+) -> Result<()> {
+    use rodio::{Decoder, Sink, Source};
+    
+    let client = sound_client()?;
+    let response = with_read_timeout(client.get(url).send()).await?;
+    
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("Failed to download audio file: {}", response.status()));
+    }
+    
+    // Decode while the rest of the file is still downloading
+    let buffer = Arc::new(SoundBuffer::new(response.content_length()));
+    let download = tokio::spawn(download_sound(
+        client,
+        url.to_string(),
+        response,
+        Arc::clone(&buffer),
+        verbose,
+    ));
+    
+    let reader = SoundStreamReader { buffer, pos: 0 };
+    let device = device.map(str::to_string);
+    let playback = tokio::task::spawn_blocking(move || -> Result<()> {
+        let (_stream, stream_handle) = open_sound_output(device.as_deref())?;
+        let sink = Sink::try_new(&stream_handle)?;
+        
+        // Repeats replay the buffered samples, the file is only downloaded once
+        let source = Decoder::new(reader)?;
+        let total = append_repeated(&sink, source.amplify(gain), repeats, delay);
+        wait_for_sink(&sink, total, progress);
+        Ok(())
+    })
+    .await;
+    
+    download.abort();
+    playback??;
     
     if verbose {
-        println!("✓ Played sound from URL: {}", url);
+        info!("✓ Played sound from URL: {}", url);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum AudioHost {
+    /// The platform's default (ALSA on Linux, CoreAudio on macOS)
+    Default,
+    /// JACK on Linux/BSD, needs a build with `--features jack`
+    Jack,
+}
+
+fn audio_host(kind: AudioHost) -> Result<cpal::Host> {
+    match kind {
+        AudioHost::Default => Ok(cpal::default_host()),
+        AudioHost::Jack => jack_host(),
+    }
+}
+
+#[cfg(all(feature = "jack", any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd")))]
+fn jack_host() -> Result<cpal::Host> {
+    cpal::host_from_id(cpal::HostId::Jack)
+        .map_err(|e| anyhow::anyhow!("JACK is unavailable: {}", e))
+}
+
+#[cfg(not(all(feature = "jack", any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd"))))]
+fn jack_host() -> Result<cpal::Host> {
+    Err(anyhow::anyhow!("This beep was built without JACK support (rebuild with --features jack)"))
+}
+
+/// Where and how the synthesized beep is played
+struct BeepOutput {
+    host: AudioHost,
+    /// From --device, wins over the configured `device_id`
+    device_name: Option<String>,
+    device_id: Option<String>,
+    buffer_ms: Option<u64>,
+    max_volume: HashMap<String, f32>,
+}
+
+impl BeepOutput {
+    fn new(args: &Args, config: Option<&Config>) -> Self {
+        let audio = config.and_then(|c| c.audio.as_ref());
+        BeepOutput {
+            host: args.audio_host,
+            device_name: args.device.clone(),
+            device_id: audio.and_then(|a| a.device_id.clone()),
+            buffer_ms: args.buffer_ms,
+            max_volume: audio.and_then(|a| a.max_volume.clone()).unwrap_or_default(),
+        }
+    }
+    
+    /// Configured volume ceiling for a device, the lowest one if several names match
+    fn volume_ceiling(&self, device_name: &str) -> Option<f32> {
+        let device_name = device_name.to_lowercase();
+        self.max_volume
+            .iter()
+            .filter(|(name, _)| device_name.contains(&name.to_lowercase()))
+            .map(|(_, &max)| max.clamp(0.0, 1.0))
+            .reduce(f32::min)
+    }
+    
+    fn device(&self) -> Result<cpal::Device> {
+        let host = audio_host(self.host)?;
+        if let Some(name) = &self.device_name {
+            return find_output_device(&host, name);
+        }
+        if let Some(id) = &self.device_id {
+            return find_device_by_id(&host, id);
+        }
+        
+        host.default_output_device().ok_or_else(|| match self.host {
+            // cpal only finds a JACK device while it can connect to the server
+            AudioHost::Jack => anyhow::anyhow!("No JACK output, is the JACK server running?"),
+            AudioHost::Default => anyhow::anyhow!("No audio device available"),
+        })
+    }
+}
+
+/// Stable part of a device name, where the platform has one
+///
+/// cpal has no device IDs, but ALSA names carry the card's ID (`CARD=PCH,DEV=0`),
+/// which unlike card numbers and descriptions survives reboots and new devices.
+fn stable_device_id(name: &str) -> Option<&str> {
+    name.find("CARD=").map(|start| &name[start..])
+}
+
+fn find_device_by_id(host: &cpal::Host, id: &str) -> Result<cpal::Device> {
+    let devices: Vec<(String, cpal::Device)> = host
+        .output_devices()?
+        .map(|device| (device.name().unwrap_or_default(), device))
+        .collect();
+    
+    if let Some((_, device)) = devices.iter().find(|(name, _)| stable_device_id(name) == Some(id)) {
+        return Ok(device.clone());
+    }
+    
+    // Platforms without IDs only have names to go by
+    let needle = id.to_lowercase();
+    if let Some((_, device)) = devices.iter().find(|(name, _)| name.to_lowercase().contains(&needle)) {
+        return Ok(device.clone());
+    }
+    
+    let available: Vec<&str> = devices
+        .iter()
+        .map(|(name, _)| stable_device_id(name).unwrap_or(name))
+        .collect();
+    Err(anyhow::anyhow!(
+        "No output device with ID '{}'. Available: {}",
+        id,
+        available.join(", ")
+    ))
+}
+
+fn generate_beep_tone(
+    frequency: f32,
+    duration_ms: u64,
+    tone: &ToneOptions,
+    gain: f32,
+    output: &BeepOutput,
+) -> Result<()> {
+    generate_notes(&[Note { frequency: Some(frequency), duration_ms }], tone, gain, output)
+}
+
+/// Play a sequence of notes and rests through a single stream, so melodies have no gaps
+fn generate_notes(notes: &[Note], tone: &ToneOptions, gain: f32, output: &BeepOutput) -> Result<()> {
+    let device = output.device()?;
+    let ceiling = output.volume_ceiling(&device.name().unwrap_or_default());
+    
+    // JACK reports the server's own sample rate and buffer size, which the stream has to use
+    let supported = device.default_output_config()?;
+    let config = stream_config_with_buffer(&supported, output.buffer_ms)?;
+    
+    match supported.sample_format() {
+        SampleFormat::F32 => run_beep::<f32>(&device, &config, notes, tone, gain, ceiling),
+        SampleFormat::I16 => run_beep::<i16>(&device, &config, notes, tone, gain, ceiling),
+        SampleFormat::U16 => run_beep::<u16>(&device, &config, notes, tone, gain, ceiling),
+        _ => Err(anyhow::anyhow!("Unsupported sample format")),
+    }
+}
+
+/// Stream config asking for a fixed buffer of `buffer_ms`, if the device supports that size
+fn stream_config_with_buffer(
+    supported: &cpal::SupportedStreamConfig,
+    buffer_ms: Option<u64>,
+) -> Result<cpal::StreamConfig> {
+    let mut config: cpal::StreamConfig = supported.config();
+    let Some(buffer_ms) = buffer_ms else {
+        return Ok(config);
+    };
+    
+    let sample_rate = config.sample_rate.0 as u64;
+    let frames = u32::try_from(sample_rate * buffer_ms / 1000)
+        .map_err(|_| anyhow::anyhow!("--buffer-ms {} is too large", buffer_ms))?;
+    
+    if let cpal::SupportedBufferSize::Range { min, max } = *supported.buffer_size() {
+        if !(min..=max).contains(&frames) {
+            return Err(anyhow::anyhow!(
+                "--buffer-ms {} is outside the device's supported {}-{} ms",
+                buffer_ms,
+                min as u64 * 1000 / sample_rate,
+                max as u64 * 1000 / sample_rate
+            ));
+        }
+    }
+    
+    config.buffer_size = cpal::BufferSize::Fixed(frames);
+    Ok(config)
+}
+
+/// Outcome of the audio probe: the default device's name, or why audio is unusable
+static AUDIO_PROBE: OnceLock<Result<String, String>> = OnceLock::new();
+
+/// Check once per process, without making a sound, that a beep could be played
+///
+/// Builds (but never starts) a stream on the default device, which fails fast
+/// on hosts with a broken audio stack instead of timing out mid-beep.
+fn probe_audio(output: &BeepOutput) -> Result<&'static str, &'static str> {
+    let probe = AUDIO_PROBE.get_or_init(|| {
+        let check = || -> Result<String> {
+            let device = output.device()?;
+            let config = device.default_output_config()?;
+            
+            // Dropped right away, the stream is never played
+            let _stream = match config.sample_format() {
+                SampleFormat::F32 => build_silent_stream::<f32>(&device, &config.into())?,
+                SampleFormat::I16 => build_silent_stream::<i16>(&device, &config.into())?,
+                SampleFormat::U16 => build_silent_stream::<u16>(&device, &config.into())?,
+                _ => return Err(anyhow::anyhow!("Unsupported sample format")),
+            };
+            Ok(device.name().unwrap_or_else(|_| "unknown".to_string()))
+        };
+        check().map_err(|e| e.to_string())
+    });
+    
+    probe.as_deref().map_err(String::as_str)
+}
+
+fn build_silent_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig) -> Result<cpal::Stream>
+where
+    T: Sample + cpal::SizedSample + Send + 'static,
+{
+    let stream = device.build_output_stream(
+        config,
+        |data: &mut [T], _: &cpal::OutputCallbackInfo| data.fill(T::EQUILIBRIUM),
+        |err| error!("Audio stream error: {}", err),
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// Whether the audio probe passed, reporting the outcome
+fn audio_available(output: &BeepOutput, verbose: bool) -> bool {
+    match probe_audio(output) {
+        Ok(device) => {
+            if verbose {
+                info!("🔈 Audio probe OK ({})", device);
+            }
+            true
+        }
+        Err(e) => {
+            error!("Audio unavailable ({}), falling back to terminal bell", e);
+            false
+        }
+    }
+}
+
+/// Shift `frequency` by a musical interval, `freq * 2^(cents/1200)`
+fn shift_frequency(frequency: f32, semitones: Option<f32>, cents: Option<f32>) -> f32 {
+    let cents = semitones.unwrap_or(0.0) * 100.0 + cents.unwrap_or(0.0);
+    frequency * 2f32.powf(cents / 1200.0)
+}
+
+/// Frequency and beep count for a progress percentage
+///
+/// The pitch rises two octaves from 400 Hz so equal progress steps sound
+/// like equal intervals, and every 25% adds a beep (1 to 5).
+fn progress_tone(percent: u8) -> (f32, u32) {
+    let fraction = percent as f32 / 100.0;
+    (400.0 * 4f32.powf(fraction), 1 + percent as u32 / 25)
+}
+
+/// Watches for snooze requests (Enter on a terminal or a snooze file) during repeats
+struct Snooze {
+    duration: Duration,
+    file: Option<PathBuf>,
+    keypresses: Option<std::sync::mpsc::Receiver<()>>,
+}
+
+impl Snooze {
+    fn new(seconds: u64, file: Option<PathBuf>) -> Self {
+        use std::io::{BufRead, IsTerminal};
+        
+        // Piped stdin isn't a user at the keyboard, only listen on a terminal
+        let keypresses = std::io::stdin().is_terminal().then(|| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                for line in std::io::stdin().lock().lines() {
+                    if line.is_err() || tx.send(()).is_err() {
+                        break;
+                    }
+                }
+            });
+            rx
+        });
+        
+        Snooze {
+            duration: Duration::from_secs(seconds),
+            file,
+            keypresses,
+        }
+    }
+    
+    /// Whether a snooze was requested since the last check
+    fn requested(&self) -> bool {
+        let mut requested = false;
+        
+        if let Some(keypresses) = &self.keypresses {
+            while keypresses.try_recv().is_ok() {
+                requested = true;
+            }
+        }
+        
+        if let Some(file) = &self.file {
+            if file.exists() {
+                let _ = fs::remove_file(file);
+                requested = true;
+            }
+        }
+        
+        requested
+    }
+}
+
+/// Tiny HTTP server whose secret URL stops the beep when opened from a notification
+struct AckServer {
+    url: String,
+    acked: Arc<AtomicBool>,
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl AckServer {
+    /// Start listening on `addr` until acknowledged, `timeout` passes or `stop` is called
+    fn start(addr: SocketAddr, public_url: Option<&str>, timeout: Duration) -> Result<Self> {
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::{Body, Response, StatusCode};
+        use std::convert::Infallible;
+        
+        let path = format!("/ack/{}", ack_token());
+        let acked = Arc::new(AtomicBool::new(false));
+        let acked_notify = Arc::new(tokio::sync::Notify::new());
+        
+        let make_service = {
+            let path = path.clone();
+            let acked = Arc::clone(&acked);
+            let acked_notify = Arc::clone(&acked_notify);
+            make_service_fn(move |_| {
+                let path = path.clone();
+                let acked = Arc::clone(&acked);
+                let acked_notify = Arc::clone(&acked_notify);
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                        let response = if request.uri().path() == path {
+                            acked.store(true, Ordering::Relaxed);
+                            acked_notify.notify_one();
+                            Response::new(Body::from("Acknowledged, beep stopped\n"))
+                        } else {
+                            let mut response = Response::new(Body::from("Not found\n"));
+                            *response.status_mut() = StatusCode::NOT_FOUND;
+                            response
+                        };
+                        async move { Ok::<_, Infallible>(response) }
+                    }))
+                }
+            })
+        };
+        
+        let server = hyper::Server::try_bind(&addr)
+            .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", addr, e))?
+            .serve(make_service);
+        let local_addr = server.local_addr();
+        
+        let (shutdown, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let stop = async move {
+                tokio::select! {
+                    _ = shutdown_rx => {}
+                    _ = acked_notify.notified() => {}
+                    _ = sleep(timeout) => {}
+                }
+            };
+            if let Err(e) = server.with_graceful_shutdown(stop).await {
+                error!("Acknowledge server error: {}", e);
+            }
+        });
+        
+        // A wildcard address can't be opened from elsewhere, use the hostname instead
+        let base = match public_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None if local_addr.ip().is_unspecified() => {
+                let host = hostname::get()
+                    .map(|h| h.to_string_lossy().into_owned())
+                    .unwrap_or_else(|_| "localhost".to_string());
+                format!("http://{}:{}", host, local_addr.port())
+            }
+            None => format!("http://{}", local_addr),
+        };
+        
+        Ok(AckServer {
+            url: format!("{}{}", base, path),
+            acked,
+            shutdown: Some(shutdown),
+            task,
+        })
+    }
+    
+    fn acked(&self) -> bool {
+        self.acked.load(Ordering::Relaxed)
+    }
+    
+    /// Shut the server down (if it hasn't already) and wait for it
+    async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = self.task.await;
+    }
+}
+
+/// Hard-to-guess path segment for the acknowledge URL
+fn ack_token() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    
+    // RandomState is seeded randomly per process, good enough for a throwaway link
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default());
+    hasher.write_u32(std::process::id());
+    format!("{:016x}", hasher.finish())
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Waveform {
+    #[default]
+    Sine,
+    Square,
+    Triangle,
+    Sawtooth,
+}
+
+/// Full-scale `waveform` value at `phase` (in cycles, 0.0-1.0)
+///
+/// All shapes are in phase with the sine: rising through zero at the start
+/// of each cycle (square jumps straight to the top).
+fn tone_sample(phase: f32, waveform: Waveform) -> f32 {
+    match waveform {
+        Waveform::Sine => (phase * 2.0 * std::f32::consts::PI).sin(),
+        Waveform::Square => (phase * 2.0 * std::f32::consts::PI).sin().signum(),
+        Waveform::Triangle => 1.0 - 4.0 * ((phase + 0.25).fract() - 0.5).abs(),
+        Waveform::Sawtooth => 2.0 * (phase + 0.5).fract() - 1.0,
+    }
+}
+
+/// Phase accumulator carried from one tone to the next
+///
+/// Advancing the phase per sample, rather than deriving it from a per-tone
+/// sample clock, lets consecutive notes join without a jump in the waveform.
+#[derive(Debug, Default, Clone, Copy)]
+struct Oscillator {
+    /// Position in the current cycle, 0.0-1.0
+    phase: f32,
+}
+
+impl Oscillator {
+    fn next_sample(&mut self, frequency: f32, sample_rate: f32, waveform: Waveform) -> f32 {
+        let value = tone_sample(self.phase, waveform);
+        self.phase = (self.phase + frequency / sample_rate).fract();
+        value
+    }
+}
+
+/// One tone of a beep sequence, `frequency` is `None` for a rest
+#[derive(Debug, Clone, Copy)]
+struct Note {
+    frequency: Option<f32>,
+    duration_ms: u64,
+}
+
+/// Tone amplitude unless --volume says otherwise
+const DEFAULT_VOLUME: f32 = 0.3;
+/// Fade at each end of a tone unless --fade-ms says otherwise
+const DEFAULT_FADE_MS: u64 = 5;
+
+/// Tone settings parsed once at startup and shared by every alert
+struct ToneOptions {
+    expr: Option<meval::Expr>,
+    melody: Option<Vec<Note>>,
+    waveform: Waveform,
+    /// Amplitude of the synthesized tone, 0.0-1.0
+    volume: f32,
+    /// Loudness to normalize to instead, overrides `volume`
+    target_rms: Option<f32>,
+    fade_ms: u64,
+    /// Frequencies in Hz sounding together on the plain beep, empty to play each note's own
+    chord: Vec<f32>,
+    /// Stereo position, -1.0 (left) to 1.0 (right)
+    pan: f32,
+}
+
+impl Default for ToneOptions {
+    fn default() -> Self {
+        ToneOptions {
+            expr: None,
+            melody: None,
+            waveform: Waveform::default(),
+            volume: DEFAULT_VOLUME,
+            target_rms: None,
+            fade_ms: DEFAULT_FADE_MS,
+            chord: Vec::new(),
+            pan: 0.0,
+        }
+    }
+}
+
+impl ToneOptions {
+    /// Notes played for a single repeat
+    fn notes(&self, args: &Args) -> Vec<Note> {
+        match &self.melody {
+            Some(melody) => melody.clone(),
+            None => vec![Note {
+                frequency: args.frequency.first().copied(),
+                duration_ms: args.length,
+            }],
+        }
+    }
+}
+
+/// Range of human hearing, notes outside it need --allow-inaudible
+const MIN_AUDIBLE_HZ: f32 = 20.0;
+const MAX_AUDIBLE_HZ: f32 = 20_000.0;
+
+/// Frequency of a note like "A4", "C#5" or "Bb3" (equal temperament, A4 = 440 Hz)
+fn note_to_frequency(note: &str, allow_inaudible: bool) -> Result<f32> {
+    let mut chars = note.chars();
+    let letter = chars
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Empty note"))?
+        .to_ascii_uppercase();
+    
+    let base = match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return Err(anyhow::anyhow!("Invalid note name '{}'", note)),
+    };
+    
+    let rest = chars.as_str();
+    let (accidental, octave) = if let Some(octave) = rest.strip_prefix('#') {
+        (1, octave)
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        (-1, octave)
+    } else {
+        (0, rest)
+    };
+    
+    let octave: i32 = octave
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid octave in note '{}'", note))?;
+    
+    // MIDI numbering: C4 = 60, A4 = 69 (in floats so absurd octaves can't overflow)
+    let midi = (octave as f32 + 1.0) * 12.0 + (base + accidental) as f32;
+    let frequency = 440.0 * 2f32.powf((midi - 69.0) / 12.0);
+    if !frequency.is_finite() || frequency <= 0.0 {
+        return Err(anyhow::anyhow!("Octave out of range in note '{}'", note));
+    }
+    
+    check_audible(&format!("Note '{}'", note), frequency, allow_inaudible)?;
+    Ok(frequency)
+}
+
+/// Catches typos like C40 for C4 before they reach the speaker
+fn check_audible(what: &str, frequency: f32, allow_inaudible: bool) -> Result<()> {
+    if !allow_inaudible && !(MIN_AUDIBLE_HZ..=MAX_AUDIBLE_HZ).contains(&frequency) {
+        return Err(anyhow::anyhow!(
+            "{} is {:.2} Hz, outside the audible {}-{} Hz range (use --allow-inaudible to play it anyway)",
+            what,
+            frequency,
+            MIN_AUDIBLE_HZ,
+            MAX_AUDIBLE_HZ
+        ));
+    }
+    Ok(())
+}
+
+/// Frequency of a melody pitch: a note name, a plain number of Hz, or `None` for "R"
+fn parse_pitch(pitch: &str, allow_inaudible: bool) -> Result<Option<f32>> {
+    if pitch.eq_ignore_ascii_case("r") {
+        return Ok(None);
+    }
+    
+    match pitch.parse::<f32>() {
+        Ok(frequency) if frequency.is_finite() && frequency > 0.0 => {
+            check_audible(&format!("Frequency {}", pitch), frequency, allow_inaudible)?;
+            Ok(Some(frequency))
+        }
+        Ok(_) => Err(anyhow::anyhow!("Invalid frequency '{}'", pitch)),
+        Err(_) => note_to_frequency(pitch, allow_inaudible).map(Some),
+    }
+}
+
+/// Parse a melody into notes
+///
+/// Tokens are separated by spaces or commas and are either `LEN:NOTE`, with
+/// lengths relative to `bpm` quarter notes ("q:C4 q:E4 h:G4"), or
+/// `PITCH:MS` with a note name or frequency and a length in milliseconds
+/// ("A4:200,C5:150,880:300"). Both kinds can be mixed.
+fn parse_melody(melody: &str, bpm: u32, allow_inaudible: bool) -> Result<Vec<Note>> {
+    let quarter_ms = 60_000.0 / bpm as f64;
+    
+    let notes = melody
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            let (first, second) = token
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid melody token '{}', expected LEN:NOTE or NOTE:MS", token))?;
+            
+            let beats = match first {
+                "w" => Some(4.0),
+                "h" => Some(2.0),
+                "q" => Some(1.0),
+                "e" => Some(0.5),
+                "s" => Some(0.25),
+                _ => None,
+            };
+            
+            let (pitch, duration_ms) = match beats {
+                Some(beats) => (second, (beats * quarter_ms).round() as u64),
+                None => {
+                    let duration_ms = second.parse::<u64>().map_err(|_| {
+                        anyhow::anyhow!("Invalid duration '{}' in melody token '{}', expected milliseconds", second, token)
+                    })?;
+                    (first, duration_ms)
+                }
+            };
+            
+            let frequency = parse_pitch(pitch, allow_inaudible)
+                .map_err(|e| anyhow::anyhow!("{} in melody token '{}'", e, token))?;
+            
+            Ok(Note { frequency, duration_ms })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    
+    if notes.is_empty() {
+        return Err(anyhow::anyhow!("Melody is empty"));
+    }
+    Ok(notes)
+}
+
+fn parse_tone_expr(s: &str) -> Result<meval::Expr> {
+    let expr: meval::Expr = s
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid --expr '{}': {}", s, e))?;
+    
+    // Catch unknown variables/functions before anything is sent
+    if let Err(e) = expr.clone().bind("t") {
+        return Err(anyhow::anyhow!("Invalid --expr '{}': {}", s, e));
+    }
+    Ok(expr)
+}
+
+/// Render a sequence of notes and rests as one continuous signal of volume-scaled
+/// mono samples, without touching an audio device
+///
+/// Used by the live stream, raw PCM output and headless mode alike, so the
+/// synthesis can be checked sample by sample.
+///
+/// The oscillator phase carries over from note to note, and back-to-back
+/// notes are faded only at the edges of the run, so melodies glide from one
+/// pitch to the next without a click.
+fn render_note_samples(notes: &[Note], sample_rate: u32, tone: &ToneOptions) -> Result<Vec<f32>> {
+    let sample_rate = sample_rate as f32;
+    let sample_count = |duration_ms: u64| (sample_rate * (duration_ms as f32 / 1000.0)) as usize;
+    let fade_samples = sample_count(tone.fade_ms);
+    
+    let func = tone.expr.clone().map(|expr| expr.bind("t")).transpose()?;
+    let mut oscillators = vec![Oscillator::default(); tone.chord.len().max(1)];
+    let mut samples = Vec::new();
+    // Start of the current run of notes without a rest between them
+    let mut run_start = 0;
+    
+    // With a target RMS the tone is synthesized at full scale and normalized afterwards
+    let volume = if tone.target_rms.is_some() { 1.0 } else { tone.volume };
+    // Power of the sounding notes alone, rests and fades would drag the RMS down
+    let mut sum_squares = 0.0f64;
+    let mut sounding_samples = 0;
+    
+    for note in notes {
+        let total_samples = sample_count(note.duration_ms);
+        let Some(frequency) = note.frequency else {
+            apply_fade(&mut samples[run_start..], fade_samples);
+            samples.extend(std::iter::repeat_n(0.0, total_samples));
+            run_start = samples.len();
+            continue;
+        };
+        
+        let note_start = samples.len();
+        match &func {
+            Some(func) => samples.extend((0..total_samples).map(|n| {
+                let value = func(n as f64 / sample_rate as f64);
+                // Keep NaN/infinite or runaway results from reaching the device
+                let value = if value.is_finite() { value.clamp(-1.0, 1.0) } else { 0.0 };
+                value as f32 * volume
+            })),
+            // Chord tones are averaged so they peak no higher than a single one
+            None => {
+                let frequencies = if tone.chord.is_empty() { std::slice::from_ref(&frequency) } else { &tone.chord[..] };
+                samples.extend((0..total_samples).map(|_| {
+                    let sum: f32 = oscillators
+                        .iter_mut()
+                        .zip(frequencies)
+                        .map(|(oscillator, &frequency)| oscillator.next_sample(frequency, sample_rate, tone.waveform))
+                        .sum();
+                    sum / frequencies.len() as f32 * volume
+                }))
+            }
+        }
+        sum_squares += samples[note_start..].iter().map(|&v| (v as f64).powi(2)).sum::<f64>();
+        sounding_samples += total_samples;
+    }
+    
+    apply_fade(&mut samples[run_start..], fade_samples);
+    
+    if let Some(target_rms) = tone.target_rms {
+        let rms = (sum_squares / sounding_samples.max(1) as f64).sqrt() as f32;
+        let peak = samples.iter().fold(0.0f32, |peak, &v| peak.max(v.abs()));
+        if rms > 0.0 {
+            // Never past full scale: a sine tops out at an RMS of about 0.707
+            let scale = (target_rms / rms).min(1.0 / peak);
+            samples.iter_mut().for_each(|v| *v *= scale);
+        }
+    }
+    Ok(samples)
+}
+
+/// Ramp the first and last `fade_samples` linearly from and to silence
+///
+/// The fade is capped at half the tone so the two ramps never overlap.
+fn apply_fade(samples: &mut [f32], fade_samples: usize) {
+    let total_samples = samples.len();
+    let fade_samples = fade_samples.min(total_samples / 2);
+    
+    for samples_played in 0..fade_samples {
+        let level = samples_played as f32 / fade_samples as f32;
+        samples[samples_played] *= level;
+        samples[total_samples - 1 - samples_played] *= level;
+    }
+}
+
+/// Volume of repeat `i` from --repeat-volumes, the last entry holds for later repeats
+fn repeat_volume(volumes: &[f32], i: u32) -> f32 {
+    volumes
+        .get(i as usize)
+        .or(volumes.last())
+        .copied()
+        .unwrap_or(1.0)
+}
+
+/// Sample rate of the PCM written by --output-fd and --output-file
+const PCM_SAMPLE_RATE: u32 = 44_100;
+
+/// Render the whole beep sequence, repeats and delays included, as 16-bit mono PCM
+fn render_beep_pcm(
+    notes: &[Note],
+    repeats: u32,
+    delay_ms: u64,
+    tone: &ToneOptions,
+    gain: f32,
+    repeat_volumes: &[f32],
+) -> Result<Vec<i16>> {
+    let silence = (PCM_SAMPLE_RATE as f32 * (delay_ms as f32 / 1000.0)) as usize;
+    let samples = render_note_samples(notes, PCM_SAMPLE_RATE, tone)?;
+    let mut pcm = Vec::with_capacity((samples.len() + silence) * repeats as usize);
+    
+    for i in 0..repeats {
+        if i > 0 {
+            pcm.resize(pcm.len() + silence, 0);
+        }
+        
+        let gain = gain * repeat_volume(repeat_volumes, i);
+        pcm.extend(samples.iter().map(|&value| i16::from_sample((value * gain).clamp(-1.0, 1.0))));
+    }
+    Ok(pcm)
+}
+
+/// Write PCM as raw signed 16-bit little-endian samples
+#[cfg(unix)]
+fn write_beep_pcm(fd: i32, pcm: &[i16]) -> Result<()> {
+    use std::io::{BufWriter, Write};
+    use std::mem::ManuallyDrop;
+    use std::os::unix::io::FromRawFd;
+    
+    // The descriptor belongs to the parent process, so never close it
+    let file = ManuallyDrop::new(unsafe { fs::File::from_raw_fd(fd) });
+    let mut out = BufWriter::new(&*file);
+    
+    for sample in pcm {
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_beep_pcm(_fd: i32, _pcm: &[i16]) -> Result<()> {
+    Err(anyhow::anyhow!("--output-fd is only supported on Unix"))
+}
+
+/// Write PCM as a 16-bit mono WAV file
+fn write_beep_wav(path: &Path, pcm: &[i16]) -> Result<()> {
+    use std::io::{BufWriter, Write};
+    
+    let data_len = (pcm.len() * 2) as u32;
+    let mut out = BufWriter::new(fs::File::create(path)?);
+    
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVE")?;
+    
+    // PCM format chunk: 1 channel, 16 bits per sample
+    out.write_all(b"fmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&PCM_SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(PCM_SAMPLE_RATE * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for sample in pcm {
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    
+    out.flush()?;
+    Ok(())
+}
+
+fn run_beep<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    notes: &[Note],
+    tone: &ToneOptions,
+    gain: f32,
+    ceiling: Option<f32>,
+) -> Result<()>
+where
+    T: Sample + cpal::SizedSample + Send + 'static,
+    T: FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    
+    // Rendered up front: meval closures aren't Send and the callback stays trivial
+    let samples = render_note_samples(notes, config.sample_rate.0, tone)?;
+    let duration_ms: u64 = notes.iter().map(|note| note.duration_ms).sum();
+    
+    // The device's ceiling caps the loudest sample, after volume and gain
+    let peak = samples.iter().fold(0.0f32, |peak, &v| peak.max(v.abs())) * gain;
+    let gain = match ceiling {
+        Some(max) if peak > max => gain * max / peak,
+        _ => gain,
+    };
+    let mut samples_played = 0;
+    
+    // Balance rather than constant power: the centre keeps both sides at full level
+    // as before, moving off it turns the far side down
+    let (left, right) = match channels {
+        1 => (1.0, 1.0),
+        _ => ((1.0 - tone.pan).min(1.0), (1.0 + tone.pan).min(1.0)),
+    };
+    
+    // The callback that writes the last sample works out when that sample will actually
+    // be heard: the stream's reported output latency plus its position in the buffer
+    let sample_rate = config.sample_rate.0 as u64;
+    let (done_tx, done_rx) = std::sync::mpsc::channel::<Instant>();
+    let mut signalled = false;
+    
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+            let frames = data.len() / channels;
+            if !signalled && samples_played + frames >= samples.len() {
+                let frames_left = samples.len().saturating_sub(samples_played) as u64;
+                let timestamp = info.timestamp();
+                let latency = timestamp
+                    .playback
+                    .duration_since(&timestamp.callback)
+                    .filter(|latency| !latency.is_zero())
+                    // Backends that don't report one still have about a buffer queued
+                    .unwrap_or(Duration::from_millis(frames as u64 * 1000 / sample_rate));
+                let _ = done_tx.send(Instant::now() + latency + Duration::from_millis(frames_left * 1000 / sample_rate));
+                signalled = true;
+            }
+            
+            for frame in data.chunks_mut(channels) {
+                // Gain comes last so it scales whatever was synthesized
+                let value = samples.get(samples_played).map(|&value| value * gain);
+                
+                for (channel, sample_out) in frame.iter_mut().enumerate() {
+                    // Channels past the front pair follow the quieter side so they don't undo the pan
+                    let level = match channel {
+                        0 => left,
+                        1 => right,
+                        _ => left.min(right),
+                    };
+                    *sample_out = match value {
+                        Some(value) => T::from_sample((value * level).clamp(-1.0, 1.0)),
+                        None => T::EQUILIBRIUM,
+                    };
+                }
+                
+                samples_played += 1;
+            }
+        },
+        |err| error!("Audio stream error: {}", err),
+        None,
+    )?;
+    
+    // A larger fixed buffer can hold back callbacks by up to its length
+    let buffer_ms = match config.buffer_size {
+        cpal::BufferSize::Fixed(frames) => frames as u64 * 1000 / config.sample_rate.0 as u64,
+        cpal::BufferSize::Default => 0,
+    };
+    
+    stream.play()?;
+    
+    // The timeout only guards against a stalled backend that stops calling back
+    let timeout = Duration::from_millis(duration_ms + buffer_ms + 1000);
+    if let Ok(heard_at) = done_rx.recv_timeout(timeout) {
+        std::thread::sleep(heard_at.saturating_duration_since(Instant::now()));
+    }
+    
+    Ok(())
+}
+
+fn print_latency_report(host: AudioHost) -> Result<()> {
+    let host = audio_host(host)?;
+    let default_name = host.default_output_device().and_then(|d| d.name().ok());
+    
+    println!("Audio startup latency (stream.play() to first callback):");
+    for device in host.output_devices()? {
+        let name = device.name().unwrap_or_else(|_| "unknown".to_string());
+        let marker = if default_name.as_deref() == Some(name.as_str()) { " (default)" } else { "" };
+        
+        match measure_startup_latency(&device) {
+            Ok(latency) => println!("  {:>8.2} ms  {}{}", latency.as_secs_f64() * 1000.0, name, marker),
+            Err(e) => println!("  {:>8}     {}{} ({})", "failed", name, marker, e),
+        }
+    }
+    
+    Ok(())
+}
+
+fn print_device_list(host: AudioHost, verbose: bool) -> Result<()> {
+    let host = audio_host(host)?;
+    let default_name = host.default_output_device().and_then(|d| d.name().ok());
+    
+    println!("Output devices:");
+    for device in host.output_devices()? {
+        let name = device.name().unwrap_or_else(|_| "unknown".to_string());
+        let marker = if default_name.as_deref() == Some(name.as_str()) { " (default)" } else { "" };
+        println!("  {}{}", name, marker);
+        
+        if verbose {
+            match device.default_output_config() {
+                Ok(config) => println!(
+                    "      {}, {} Hz, {} ch",
+                    config.sample_format(),
+                    config.sample_rate().0,
+                    config.channels()
+                ),
+                Err(e) => println!("      no default config ({})", e),
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+fn measure_startup_latency(device: &cpal::Device) -> Result<Duration> {
+    let config = device.default_output_config()?;
+    
+    match config.sample_format() {
+        SampleFormat::F32 => measure_stream_latency::<f32>(device, &config.into()),
+        SampleFormat::I16 => measure_stream_latency::<i16>(device, &config.into()),
+        SampleFormat::U16 => measure_stream_latency::<u16>(device, &config.into()),
+        _ => Err(anyhow::anyhow!("Unsupported sample format")),
+    }
+}
+
+fn measure_stream_latency<T>(device: &cpal::Device, config: &cpal::StreamConfig) -> Result<Duration>
+where
+    T: Sample + cpal::SizedSample + Send + 'static,
+{
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut signalled = false;
+    
+    // Plays silence, we only care about when the first callback arrives
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            for sample in data.iter_mut() {
+                *sample = T::EQUILIBRIUM;
+            }
+            if !signalled {
+                let _ = tx.send(Instant::now());
+                signalled = true;
+            }
+        },
+        |err| error!("Audio stream error: {}", err),
+        None,
+    )?;
+    
+    let started = Instant::now();
+    stream.play()?;
+    
+    let first_callback = rx
+        .recv_timeout(Duration::from_secs(2))
+        .map_err(|_| anyhow::anyhow!("no callback within 2s"))?;
+    Ok(first_callback.saturating_duration_since(started))
+}
+
+/// Send to one provider, returning a Pushover emergency receipt if one was issued
+async fn send_notification(
+    provider: Provider,
+    config: &Config,
+    notification: &Notification<'_>,
+    webhook_meta: Option<&Value>,
+    insecure: bool,
+    retries: u32,
+    verbose: bool,
+) -> Result<Option<String>> {
+    let not_configured = || anyhow::anyhow!("not configured");
+    
+    let title_override = config.title(provider);
+    let notification = &Notification {
+        title: title_override.or(notification.title),
+        ..*notification
+    };
+    
+    match provider {
+        Provider::Pushover => {
+            let pushover_config = config.pushover.as_ref().ok_or_else(not_configured)?;
+            send_pushover_notification(pushover_config, notification, retries, verbose).await
+        }
+        Provider::Webhook => {
+            let webhooks: Vec<&WebhookConfig> = config.all_webhooks().collect();
+            if webhooks.is_empty() {
+                return Err(not_configured());
+            }
+            
+            let priority = notification.effective_priority();
+            let mut failures = Vec::new();
+            for webhook_config in &webhooks {
+                if let Some(min_priority) = webhook_config.min_priority.filter(|&min| priority < min) {
+                    if verbose {
+                        info!("Skipping webhook {} (priority {} < min_priority {})", webhook_config.url, priority, min_priority);
+                    }
+                    continue;
+                }
+                
+                let notification = &Notification {
+                    title: webhook_config.title.as_deref().or(notification.title),
+                    ..*notification
+                };
+                // Keep the metadata title in step with the override
+                let webhook_meta = webhook_meta.map(|meta| {
+                    let mut meta = meta.clone();
+                    if webhook_config.title.is_some() {
+                        meta["title"] = json!(notification.title);
+                    }
+                    meta
+                });
+                
+                // One failing endpoint mustn't keep the others from being notified
+                if let Err(e) = send_webhook_notification(webhook_config, notification, webhook_meta.as_ref(), insecure, retries, verbose).await {
+                    failures.push((webhook_config.url.as_str(), e));
+                }
+            }
+            
+            match failures.len() {
+                0 => Ok(None),
+                1 if webhooks.len() == 1 => Err(failures.remove(0).1),
+                failed => {
+                    let details: Vec<String> = failures
+                        .iter()
+                        .map(|(url, e)| format!("{}: {}", url, describe_delivery_error(e)))
+                        .collect();
+                    // Keep a transient cause if there is one, so the retry queue still picks it up
+                    let cause = failures.iter().position(|(_, e)| is_transient(e)).unwrap_or(0);
+                    Err(failures.remove(cause).1.context(format!("{} of {} webhooks failed ({})", failed, webhooks.len(), details.join("; "))))
+                }
+            }
+        }
+        Provider::Ifttt => {
+            let ifttt_config = config.ifttt.as_ref().ok_or_else(not_configured)?;
+            send_ifttt_notification(ifttt_config, notification, verbose).await?;
+            Ok(None)
+        }
+        Provider::Telegram => {
+            let telegram_config = config.telegram.as_ref().ok_or_else(not_configured)?;
+            send_telegram_notification(telegram_config, notification, verbose).await?;
+            Ok(None)
+        }
+        Provider::Ntfy => {
+            let ntfy_config = config.ntfy.as_ref().ok_or_else(not_configured)?;
+            send_ntfy_notification(ntfy_config, notification, verbose).await?;
+            Ok(None)
+        }
+        Provider::Discord => {
+            let discord_config = config.discord.as_ref().ok_or_else(not_configured)?;
+            send_discord_notification(discord_config, notification, verbose).await?;
+            Ok(None)
+        }
+        Provider::Slack => {
+            let slack_config = config.slack.as_ref().ok_or_else(not_configured)?;
+            send_slack_notification(slack_config, notification, verbose).await?;
+            Ok(None)
+        }
+        Provider::Command => {
+            let command_config = config.command.as_ref().ok_or_else(not_configured)?;
+            send_command_notification(command_config, notification, verbose).await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Write per-provider results in the node_exporter textfile collector format
+fn write_metrics_file(path: &Path, results: &[(Provider, bool)], delivered: Option<bool>) -> Result<()> {
+    let mut metrics = String::new();
+    
+    metrics.push_str("# HELP beep_notification_success Whether the last notification to the provider succeeded.\n");
+    metrics.push_str("# TYPE beep_notification_success gauge\n");
+    for (provider, success) in results {
+        metrics.push_str(&format!(
+            "beep_notification_success{{provider=\"{}\"}} {}\n",
+            provider.name(),
+            u8::from(*success)
+        ));
+    }
+    
+    if let Some(delivered) = delivered {
+        metrics.push_str("# HELP beep_notification_delivered Whether the last notification counted as delivered overall.\n");
+        metrics.push_str("# TYPE beep_notification_delivered gauge\n");
+        metrics.push_str(&format!("beep_notification_delivered {}\n", u8::from(delivered)));
+    }
+    
+    metrics.push_str("# HELP beep_last_run_timestamp_seconds Unix time of the last beep run.\n");
+    metrics.push_str("# TYPE beep_last_run_timestamp_seconds gauge\n");
+    metrics.push_str(&format!("beep_last_run_timestamp_seconds {}\n", Local::now().timestamp()));
+    
+    // Write then rename so the collector never reads a half-written file
+    let tmp_path = path.with_extension("prom.tmp");
+    fs::write(&tmp_path, metrics)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Greedily wrap each line of `text` at `width` columns, splitting words only if they don't fit
+fn wrap_text(text: &str, width: usize) -> String {
+    let mut wrapped: Vec<String> = Vec::new();
+    
+    for line in text.lines() {
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            let mut word = word;
+            
+            if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+                wrapped.push(std::mem::take(&mut current));
+            }
+            
+            // Words longer than a whole line are cut into line-sized pieces
+            while word.chars().count() > width {
+                if !current.is_empty() {
+                    wrapped.push(std::mem::take(&mut current));
+                }
+                let split = word.char_indices().nth(width).map_or(word.len(), |(i, _)| i);
+                wrapped.push(word[..split].to_string());
+                word = &word[split..];
+            }
+            
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        wrapped.push(current);
+    }
+    
+    wrapped.join("\n")
+}
+
+fn get_digest_path() -> PathBuf {
+    match dirs::cache_dir() {
+        Some(cache) => cache.join("beep").join("digest.jsonl"),
+        None => PathBuf::from("beep-digest.jsonl"),
+    }
+}
+
+/// Queue `message` for the next --flush-digest, returning how many are pending
+fn append_to_digest(path: &Path, message: &str) -> Result<usize> {
+    use std::io::Write;
+    
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    
+    // One JSON string per line keeps multi-line messages intact
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(message)?)?;
+    
+    Ok(fs::read_to_string(path)?.lines().count())
+}
+
+/// Remove and return every queued digest message
+fn take_digest(path: &Path) -> Result<Vec<String>> {
+    // Moved aside first so messages queued during the flush start a new digest
+    let taken = path.with_extension("flushing");
+    match fs::rename(path, &taken) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    }
+    
+    let content = fs::read_to_string(&taken)?;
+    fs::remove_file(&taken)?;
+    
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|e| anyhow::anyhow!("Corrupt digest entry: {}", e)))
+        .collect()
+}
+
+fn get_last_notification_path() -> PathBuf {
+    match dirs::cache_dir() {
+        Some(cache) => cache.join("beep").join("last.json"),
+        None => PathBuf::from("beep-last.json"),
+    }
+}
+
+/// The last dispatched notification, kept for --replay
+#[derive(Debug, Deserialize, Serialize)]
+struct LastNotification {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<Severity>,
+}
+
+fn save_last_notification(path: &Path, notification: &Notification) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    
+    let last = LastNotification {
+        message: notification.message.to_string(),
+        title: notification.title.map(str::to_string),
+        priority: notification.priority,
+        severity: notification.severity,
+    };
+    fs::write(path, serde_json::to_string_pretty(&last)?)?;
+    Ok(())
+}
+
+fn load_last_notification(path: &Path) -> Result<LastNotification> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(anyhow::anyhow!("Nothing to replay, no notification has been sent yet"));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Corrupt last notification {}: {}", path.display(), e))
+}
+
+/// Send the last notification again to every configured provider, or just --replay-to
+async fn replay_last_notification(args: &Args, config: Option<&Config>) -> Result<()> {
+    let last = load_last_notification(&get_last_notification_path())?;
+    let config = config.ok_or_else(|| anyhow::anyhow!("No notification providers configured"))?;
+    
+    let providers = match args.replay_to {
+        Some(provider) if !config.is_configured(provider) => {
+            return Err(anyhow::anyhow!("{} isn't configured", provider.label()));
+        }
+        Some(provider) => vec![provider],
+        None => config.configured_providers(),
+    };
+    
+    let notification = Notification {
+        title: last.title.as_deref(),
+        priority: last.priority,
+        severity: last.severity,
+        ..Notification::from_args(&last.message, args)
+    };
+    let webhook_meta = args.include_meta.then(|| build_webhook_meta(args, &notification));
+    
+    if args.verbose {
+        info!("Replaying \"{}\"", last.message);
+    }
+    for provider in providers {
+        if let Err(e) = send_notification(provider, config, &notification, webhook_meta.as_ref(), args.insecure, args.retries, args.verbose).await {
+            error!("✗ {} {}", provider.label(), describe_delivery_error(&e));
+        }
+    }
+    Ok(())
+}
+
+fn get_queue_path() -> PathBuf {
+    match dirs::cache_dir() {
+        Some(cache) => cache.join("beep").join("queue.json"),
+        None => PathBuf::from("beep-queue.json"),
+    }
+}
+
+/// A notification a provider failed to deliver, retried by later runs and --flush-queue
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct QueuedNotification {
+    provider: Provider,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<Severity>,
+    #[serde(default)]
+    markdown: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sound: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expire: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<Location>,
+    /// Unix seconds of the first failed attempt
+    queued_at: i64,
+}
+
+impl QueuedNotification {
+    /// Same provider and content, however often it was queued
+    fn same_as(&self, other: &QueuedNotification) -> bool {
+        self.provider == other.provider
+            && self.message == other.message
+            && self.title == other.title
+            && self.priority == other.priority
+            && self.severity == other.severity
+    }
+    
+    /// The notification as it was first sent, minus the acknowledge link of that run
+    fn notification(&self) -> Notification<'_> {
+        Notification {
+            message: &self.message,
+            title: self.title.as_deref(),
+            priority: self.priority,
+            severity: self.severity,
+            markdown: self.markdown,
+            ttl: self.ttl,
+            sound: self.sound.as_deref(),
+            retry: self.retry,
+            expire: self.expire,
+            ack_url: None,
+            location: self.location,
+        }
+    }
+}
+
+fn load_queue(path: &Path) -> Result<Vec<QueuedNotification>> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Corrupt notification queue {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save_queue(path: &Path, queue: &[QueuedNotification]) -> Result<()> {
+    if queue.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+    
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(queue)?)?;
+    Ok(())
+}
+
+/// Queue `notification` for each provider in `failed`, returning how many are pending
+fn enqueue_failed(path: &Path, notification: &Notification, failed: &[Provider]) -> Result<usize> {
+    let mut queue = load_queue(path)?;
+    let queued_at = Local::now().timestamp();
+    
+    for &provider in failed {
+        let entry = QueuedNotification {
+            provider,
+            message: notification.message.to_string(),
+            title: notification.title.map(str::to_string),
+            priority: notification.priority,
+            severity: notification.severity,
+            markdown: notification.markdown,
+            ttl: notification.ttl,
+            sound: notification.sound.map(str::to_string),
+            retry: notification.retry,
+            expire: notification.expire,
+            location: notification.location,
+            queued_at,
+        };
+        // An alert that keeps failing is only delivered once
+        if !queue.iter().any(|queued| queued.same_as(&entry)) {
+            queue.push(entry);
+        }
+    }
+    
+    save_queue(path, &queue)?;
+    Ok(queue.len())
+}
+
+/// How long a failed notification is retried when `general.queue_max_age` isn't set
+const DEFAULT_QUEUE_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// Retry the notifications queued before `queued_before` (Unix seconds), returning how
+/// many were delivered and how many are left
+async fn flush_queue(args: &Args, config: &Config, queued_before: i64) -> Result<(usize, usize)> {
+    let path = get_queue_path();
+    let queue = load_queue(&path)?;
+    if queue.is_empty() {
+        return Ok((0, 0));
+    }
+    
+    let max_age = config
+        .general
+        .as_ref()
+        .and_then(|g| g.queue_max_age)
+        .unwrap_or(DEFAULT_QUEUE_MAX_AGE_SECS);
+    let now = Local::now().timestamp();
+    
+    // Delivered and dropped entries, both leave the queue
+    let mut done = Vec::new();
+    let mut delivered = 0;
+    for entry in queue.iter().filter(|entry| entry.queued_at < queued_before) {
+        if now.saturating_sub(entry.queued_at) > max_age as i64 {
+            warn!("Dropping queued \"{}\" for {}, undelivered for over {} s", entry.message, entry.provider.label(), max_age);
+            done.push(entry);
+            continue;
+        }
+        if !config.is_configured(entry.provider) {
+            warn!("Dropping queued \"{}\", {} is no longer configured", entry.message, entry.provider.label());
+            done.push(entry);
+            continue;
+        }
+        
+        match send_notification(entry.provider, config, &entry.notification(), None, args.insecure, args.retries, args.verbose).await {
+            Ok(_) => {
+                done.push(entry);
+                delivered += 1;
+            }
+            Err(e) if is_transient(&e) => error!("✗ {} still failing: {}", entry.provider.label(), describe_delivery_error(&e)),
+            Err(e) => {
+                error!("✗ Dropping queued \"{}\", {} rejected it: {}", entry.message, entry.provider.label(), describe_delivery_error(&e));
+                done.push(entry);
+            }
+        }
+    }
+    
+    // Re-read so entries queued by a concurrent run aren't lost
+    let mut remaining = load_queue(&path)?;
+    remaining.retain(|entry| !done.iter().any(|sent| sent.same_as(entry)));
+    save_queue(&path, &remaining)?;
+    Ok((delivered, remaining.len()))
+}
+
+/// Retry what earlier runs couldn't deliver, once per run rather than with every alert
+async fn retry_queued(args: &Args, config: Option<&Config>, queued_before: i64) {
+    let Some(config) = config else {
+        return;
+    };
+    match flush_queue(args, config, queued_before).await {
+        Ok((0, _)) => {}
+        Ok((sent, left)) if args.verbose => info!("✓ Delivered {} queued notification(s), {} left", sent, left),
+        Ok(_) => {}
+        Err(e) => error!("Error flushing notification queue: {}", e),
+    }
+}
+
+fn get_throttle_path() -> PathBuf {
+    match dirs::cache_dir() {
+        Some(cache) => cache.join("beep").join("throttle.json"),
+        None => PathBuf::from("beep-throttle.json"),
+    }
+}
+
+/// When each throttled provider last delivered, in Unix milliseconds
+fn load_throttle_state(path: &Path) -> Result<HashMap<Provider, i64>> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Corrupt throttle state {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save_throttle_state(path: &Path, last_sent: &HashMap<Provider, i64>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(last_sent)?)?;
+    Ok(())
+}
+
+/// Tone of the --confirm-beep blip, high and short to stand apart from the alert
+const CONFIRM_BEEP_HZ: f32 = 2400.0;
+const CONFIRM_BEEP_MS: u64 = 40;
+
+/// Sound played with an alert, besides the beep
+enum AlertSound {
+    Builtin(BuiltinSound),
+    Url(String),
+    File(String),
+}
+
+/// Play `sound` `repeats` times, `delay` apart, like the beep. False if it failed
+async fn play_alert_sound(
+    sound: &AlertSound,
+    device: Option<&str>,
+    gain: f32,
+    repeats: u32,
+    delay: Duration,
+    progress: bool,
+    verbose: bool,
+) -> bool {
+    let (kind, result) = match sound {
+        AlertSound::Builtin(builtin) => (
+            "built-in sound",
+            play_builtin_sound(*builtin, device, gain, repeats, delay, progress, verbose),
+        ),
+        AlertSound::Url(url) => (
+            "sound from URL",
+            play_sound_url(url, device, gain, repeats, delay, progress, verbose).await,
+        ),
+        AlertSound::File(path) => (
+            "sound file",
+            play_sound_file(path, device, gain, repeats, delay, progress, verbose),
+        ),
+    };
+    
+    if let Err(e) = &result {
+        error!("Error playing {}: {}", kind, e);
+    }
+    result.is_ok()
+}
+
+/// Stand in for a sound that failed while the beep is off, following --sound-fallback
+fn play_sound_fallback(args: &Args, tone: &ToneOptions, output: &BeepOutput) {
+    if args.sound_fallback == SoundFallback::Beep {
+        if args.verbose {
+            info!("Sound failed, playing the beep instead");
+        }
+        let played = audio_available(output, args.verbose)
+            && generate_notes(&tone.notes(args), tone, args.gain, output)
+                .map_err(|e| error!("Error generating sound: {}", e))
+                .is_ok();
+        if played {
+            return;
+        }
+    }
+    
+    if args.sound_fallback != SoundFallback::None {
+        ring_bell(&args.bell_char);
+    }
+}
+
+/// Terminal bell fallback, flushed right away since the daemon modes keep running
+fn ring_bell(bell: &str) {
+    use std::io::Write;
+    
+    print!("{}", bell);
+    let _ = std::io::stdout().flush();
+}
+
+/// What a dispatch achieved, for the local alert that follows it
+#[derive(Debug, Default)]
+struct Dispatched {
+    /// Emergency Pushover receipt, for --repeat-until-ack
+    receipt: Option<String>,
+    /// Providers that confirmed delivery, one --confirm-beep blip each
+    confirmations: usize,
+    /// The `priority_order` primary, if it failed and so the alert wasn't delivered
+    failed_primary: Option<Provider>,
+}
+
+/// Send the notification to every enabled provider
+///
+/// Failures are reported and queued for the next run here, only setup errors are returned.
+async fn dispatch_notifications(
+    args: &Args,
+    config: Option<&Config>,
+    notification: &Notification<'_>,
+    webhook_meta: Option<&Value>,
+) -> Result<Dispatched> {
+    let _in_flight = DispatchGuard::new();
+    
+    // Per-provider delivery results and the overall outcome, for metrics
+    let mut results: Vec<(Provider, bool)> = Vec::new();
+    let mut delivered = None;
+    // Emergency Pushover receipt, for --repeat-until-ack
+    let mut receipt = None;
+    // Providers that failed in a way worth retrying later
+    let mut transient = Vec::new();
+    let mut failed_primary = None;
+    
+    // Report one provider's result, true if it delivered
+    let mut record = |provider: Provider, result: Result<Option<String>>| {
+        match &result {
+            Ok(Some(issued)) => receipt = Some(issued.clone()),
+            Ok(None) => {}
+            Err(e) => {
+                error!("✗ {} {}", provider.label(), describe_delivery_error(e));
+                if is_transient(e) {
+                    transient.push(provider);
+                }
+            }
+        }
+        results.push((provider, result.is_ok()));
+        result.is_ok()
+    };
+    
+    let route = match config.and_then(|c| c.routes.as_ref()) {
+        Some(routes) => select_route(routes, Local::now().time())?,
+        None => None,
+    };
+    if args.verbose {
+        if let Some(route) = route {
+            info!("Using route {} ({}-{})", route.name.as_deref().unwrap_or("unnamed"), route.from, route.to);
+        }
+    }
+    let priority = notification.effective_priority();
+    
+    // The local popup shows while the providers are contacted and isn't counted as a delivery,
+    // but routes and min_priority filter it like any provider
+    let desktop_config = config.and_then(|c| c.desktop.as_ref());
+    let desktop = (args.desktop || desktop_config.is_some_and(|d| d.enabled))
+        && route.is_none_or(|r| r.providers.contains(&RouteTarget::Desktop))
+        && match desktop_config.and_then(|d| d.min_priority) {
+            Some(min_priority) if priority < min_priority => {
+                if args.verbose {
+                    info!("Skipping desktop (priority {} < min_priority {})", priority, min_priority);
+                }
+                false
+            }
+            _ => true,
+        };
+    let popup = desktop.then(|| show_desktop_notification(notification));
+    
+    // Send notifications if configured
+    if let Some(config) = config {
+        
+        // Only touch the state file when some provider is actually throttled
+        let throttle_path = get_throttle_path();
+        let throttling = Provider::ALL.iter().any(|&p| config.throttle_ms(p).is_some());
+        let mut last_sent = if throttling {
+            load_throttle_state(&throttle_path).unwrap_or_else(|e| {
+                error!("{}", e);
+                HashMap::new()
+            })
+        } else {
+            HashMap::new()
+        };
+        let now_ms = Local::now().timestamp_millis();
+        
+        let enabled = |provider: Provider| {
+            if !route.is_none_or(|r| r.providers.contains(&RouteTarget::Provider(provider))) {
+                return false;
+            }
+            if let Some(min_priority) = config.min_priority(provider) {
+                if priority < min_priority {
+                    if args.verbose {
+                        info!("Skipping {} (priority {} < min_priority {})", provider.name(), priority, min_priority);
+                    }
+                    return false;
+                }
+            }
+            if let (Some(throttle_ms), Some(&sent)) = (config.throttle_ms(provider), last_sent.get(&provider)) {
+                let elapsed = now_ms.saturating_sub(sent);
+                if (0..throttle_ms as i64).contains(&elapsed) {
+                    if args.verbose {
+                        info!("Skipping {} (throttled, last sent {} ms ago < throttle_ms {})", provider.name(), elapsed, throttle_ms);
+                    }
+                    return false;
+                }
+            }
+            true
+        };
+        
+        if let Some(chain) = &config.fallback {
+            // Stop at the first provider that delivers
+            let mut delivered_via = None;
+            for &provider in chain.iter().filter(|&&p| enabled(p)) {
+                let result = send_notification(provider, config, notification, webhook_meta, args.insecure, args.retries, args.verbose).await;
+                if record(provider, result) {
+                    delivered_via = Some(provider);
+                    break;
+                }
+            }
+            
+            match delivered_via {
+                Some(provider) if args.verbose => info!("✓ Delivered via {}", provider.name()),
+                Some(_) => {}
+                None => error!("✗ All fallback providers failed"),
+            }
+            delivered = Some(delivered_via.is_some());
+        } else {
+            let providers: Vec<Provider> = config.configured_providers().into_iter().filter(|&p| enabled(p)).collect();
+            if args.stop_on_success {
+                for &provider in &providers {
+                    let result = send_notification(provider, config, notification, webhook_meta, args.insecure, args.retries, args.verbose).await;
+                    if record(provider, result) {
+                        if args.verbose {
+                            info!("Stopping after {} succeeded", provider.name());
+                        }
+                        break;
+                    }
+                }
+            } else {
+                // All at once, so a slow provider doesn't hold up the others
+                let sends = providers.iter().map(|&provider| {
+                    send_notification(provider, config, notification, webhook_meta, args.insecure, args.retries, args.verbose)
+                });
+                let outcomes = futures_util::future::join_all(sends).await;
+                for (&provider, result) in providers.iter().zip(outcomes) {
+                    record(provider, result);
+                }
+            }
+            
+            // With a priority_order the primary provider alone decides the outcome
+            let primary = config.priority_order.as_ref().and(providers.first().copied());
+            delivered = match primary {
+                Some(primary) => {
+                    let ok = results.contains(&(primary, true));
+                    if !ok {
+                        failed_primary = Some(primary);
+                    } else if args.verbose {
+                        info!("✓ Delivered via primary provider {}", primary.name());
+                    }
+                    Some(ok)
+                }
+                None if providers.is_empty() => None,
+                None => Some(results.iter().any(|&(_, ok)| ok)),
+            };
+        }
+        
+        let mut throttle_changed = false;
+        for &(provider, ok) in &results {
+            if ok && config.throttle_ms(provider).is_some() {
+                last_sent.insert(provider, now_ms);
+                throttle_changed = true;
+            }
+        }
+        if throttle_changed {
+            if let Err(e) = save_throttle_state(&throttle_path, &last_sent) {
+                error!("Error saving throttle state: {}", e);
+            }
+        }
+        
+        // Keep failures for the next run, unless another provider already stood in for them
+        let failed: Vec<Provider> = if config.fallback.is_some() {
+            let first_tried = results.first().map(|&(provider, _)| provider);
+            first_tried.filter(|_| delivered == Some(false)).into_iter().collect()
+        } else if args.stop_on_success && results.iter().any(|&(_, ok)| ok) {
+            Vec::new()
+        } else {
+            results.iter().filter(|&&(_, ok)| !ok).map(|&(provider, _)| provider).collect()
+        };
+        // Only outages are retried, a rejected request would just fail again
+        let failed: Vec<Provider> = failed.into_iter().filter(|provider| transient.contains(provider)).collect();
+        if !failed.is_empty() {
+            match enqueue_failed(&get_queue_path(), notification, &failed) {
+                Ok(pending) if args.verbose => info!("Queued for retry ({} pending)", pending),
+                Ok(_) => {}
+                Err(e) => error!("Error queueing failed notification: {}", e),
+            }
+        }
+    }
+    
+    // One-off webhook from the command line, on top of whatever the config sends
+    if let Some(url) = &args.webhook_url {
+        let webhook = WebhookConfig {
+            url: url.clone(),
+            min_priority: None,
+            throttle_ms: None,
+            title: None,
+            method: None,
+            headers: None,
+            client_cert: None,
+            client_key: None,
+            ca_cert: None,
+            insecure: None,
+        };
+        let result = send_webhook_notification(&webhook, notification, webhook_meta, args.insecure, args.retries, args.verbose).await;
+        if let Err(e) = &result {
+            error!("✗ {} {}", Provider::Webhook.label(), describe_delivery_error(e));
+        }
+        results.push((Provider::Webhook, result.is_ok()));
+        delivered = Some(delivered.unwrap_or(false) || result.is_ok());
+    }
+    
+    if let Some(popup) = popup {
+        match popup.await.map_err(anyhow::Error::from).and_then(|shown| shown) {
+            Ok(()) if args.verbose => info!("✓ Desktop notification shown"),
+            Ok(()) => {}
+            // Headless machines have no notification daemon, which is fine
+            Err(e) if args.verbose => warn!("Desktop notification unavailable: {}", e),
+            Err(_) => {}
+        }
+    }
+    
+    if !results.is_empty() {
+        if let Err(e) = save_last_notification(&get_last_notification_path(), notification) {
+            error!("Error saving notification for --replay: {}", e);
+        }
+    }
+    
+    if let Some(path) = &args.metrics_file {
+        if let Err(e) = write_metrics_file(path, &results, delivered) {
+            error!("Error writing metrics to {}: {}", path.display(), e);
+        } else if args.verbose {
+            info!("✓ Metrics written to {}", path.display());
+        }
+    }
+    
+    let confirmations = results.iter().filter(|&&(_, ok)| ok).count();
+    Ok(Dispatched { receipt, confirmations, failed_primary })
+}
+
+/// Gap between two --confirm-beep blips, so they can be told apart
+const CONFIRM_BEEP_GAP_MS: u64 = 80;
+
+/// One blip per delivered notification, so successes can be counted by ear
+///
+/// Played after the alert tone, on a thread of its own since playback blocks.
+async fn play_confirmations(args: &Args, config: Option<&Config>, confirmations: usize) {
+    if !args.confirm_beep || args.no_sound || args.headless || confirmations == 0 {
+        return;
+    }
+    
+    let blip = Note { frequency: Some(CONFIRM_BEEP_HZ), duration_ms: CONFIRM_BEEP_MS };
+    let gap = Note { frequency: None, duration_ms: CONFIRM_BEEP_GAP_MS };
+    let mut notes: Vec<Note> = std::iter::repeat_n([blip, gap], confirmations).flatten().collect();
+    notes.pop();
+    
+    let output = BeepOutput::new(args, config);
+    let gain = args.gain;
+    let played = tokio::task::spawn_blocking(move || generate_notes(&notes, &ToneOptions::default(), gain, &output)).await;
+    if let Err(e) = played.map_err(anyhow::Error::from).and_then(|played| played) {
+        error!("Error playing confirmation beep: {}", e);
+    }
+}
+
+/// Pop up a native desktop notification with the title and message
+///
+/// Talking to the notification daemon blocks, so this runs on a blocking
+/// thread and the returned handle yields the outcome.
+fn show_desktop_notification(notification: &Notification<'_>) -> tokio::task::JoinHandle<Result<()>> {
+    let mut popup = notify_rust::Notification::new();
+    popup
+        .appname("beep")
+        .summary(notification.title.unwrap_or("beep"))
+        .body(&notification.text_with_ack());
+    
+    #[cfg(all(unix, not(target_os = "macos")))]
+    popup.urgency(match notification.effective_priority() {
+        priority if priority < 0 => notify_rust::Urgency::Low,
+        0 => notify_rust::Urgency::Normal,
+        _ => notify_rust::Urgency::Critical,
+    });
+    
+    tokio::task::spawn_blocking(move || popup.show().map(drop).map_err(Into::into))
+}
+
+/// Play the sound and the beep, stopping early once the alert is acknowledged
+async fn play_local_alert(
+    args: &Args,
+    config: Option<&Config>,
+    tone: &ToneOptions,
+    beep_output: &BeepOutput,
+    ack: Option<&AckServer>,
+    receipt: Option<&str>,
+) {
+    // Play built-in or configured sound file
+    let sound_device = args.sound_device.as_deref();
+    let sound_file = config
+        .and_then(|c| c.sound.as_ref())
+        .and_then(|s| s.file.as_deref());
+    let pushover_config = config.and_then(|c| c.pushover.as_ref());
+    let mut acknowledged = false;
+    let mut sound_failed = false;
+    
+    let sound_config = config.and_then(|c| c.sound.as_ref());
+    let alert_sound = match (args.builtin_sound, sound_config) {
+        (Some(builtin), _) => Some(AlertSound::Builtin(builtin)),
+        (None, Some(sound)) => sound
+            .url
+            .clone()
+            .map(AlertSound::Url)
+            .or_else(|| sound.file.clone().map(AlertSound::File)),
+        (None, None) => None,
+    };
+    let sound_volume = args
+        .sound_volume
+        .or(sound_config.and_then(|s| s.volume))
+        .unwrap_or(1.0)
+        .clamp(0.0, 1.0);
+    
+    // Layered, the beep and the sound add up; scale both down so the sum can't clip
+    let layered = args.layer && alert_sound.is_some() && !args.no_sound;
+    let (beep_mix, sound_mix) = match tone.volume + sound_volume {
+        sum if layered && sum > 1.0 => (1.0 / sum, 1.0 / sum),
+        _ => (1.0, 1.0),
+    };
+    if layered && beep_mix < 1.0 && args.verbose {
+        info!("Layering beep and sound, scaled by {:.2} to avoid clipping", beep_mix);
+    }
+    let mut sound_task = None;
+    
+    if args.repeat_until_ack && (receipt.is_none() || sound_file.is_none()) {
+        error!("--repeat-until-ack needs sound.file and an emergency (-p 2) Pushover notification, playing once");
+    }
+    
+    if let (true, Some(file_path), Some(receipt), Some(pushover_config)) =
+        (args.repeat_until_ack, sound_file, receipt, pushover_config)
+    {
+        match play_sound_file_until_ack(file_path, sound_device, args.gain * sound_volume, pushover_config, receipt, args.verbose).await {
+            Ok(true) => {
+                acknowledged = true;
+                info!("✓ Acknowledged on Pushover, stopping sound");
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!("Error playing sound file: {}", e);
+                sound_failed = true;
+            }
+        }
+    } else if let Some(sound) = alert_sound {
+        let gain = args.gain * sound_volume * sound_mix;
+        let (repeats, delay) = (args.repeats, Duration::from_millis(args.delay));
+        if layered {
+            // Plays on its own thread while the beep below runs, both are mixed by the system
+            let device = args.sound_device.clone();
+            let (progress, verbose) = (args.sound_progress, args.verbose);
+            let runtime = tokio::runtime::Handle::current();
+            sound_task = Some(tokio::task::spawn_blocking(move || {
+                runtime.block_on(play_alert_sound(&sound, device.as_deref(), gain, repeats, delay, progress, verbose));
+            }));
+        } else {
+            sound_failed = !play_alert_sound(&sound, sound_device, gain, repeats, delay, args.sound_progress, args.verbose).await;
+        }
+    }
+    
+    // Write raw PCM to the inherited descriptor or a WAV file instead of the audio device
+    let notes = tone.notes(args);
+    let render_pcm = || render_beep_pcm(&notes, args.repeats, args.delay, tone, args.gain, &args.repeat_volumes);
+    if let Some(fd) = args.output_fd {
+        if let Err(e) = render_pcm().and_then(|pcm| write_beep_pcm(fd, &pcm)) {
+            error!("Error writing PCM to fd {}: {}", fd, e);
+        } else if args.verbose {
+            info!("🔊 Wrote {} beep(s) to fd {}", args.repeats, fd);
+        }
+    } else if let Some(path) = &args.output_file {
+        if let Err(e) = render_pcm().and_then(|pcm| write_beep_wav(path, &pcm)) {
+            error!("Error writing {}: {}", path.display(), e);
+        } else if args.verbose {
+            info!("🔊 Wrote {} beep(s) to {}", args.repeats, path.display());
+        }
+    } else if !args.no_sound && !args.headless && !audio_available(beep_output, args.verbose) {
+        if args.sound_fallback != SoundFallback::None {
+            ring_bell(&args.bell_char);
+        }
+    } else if args.no_sound && sound_failed && !args.headless {
+        // The beep is off, so nothing else would be heard
+        play_sound_fallback(args, tone, beep_output);
+    } else if !args.no_sound {
+        if args.pan != 0.0 && args.verbose && !args.headless {
+            let channels = beep_output.device().and_then(|device| Ok(device.default_output_config()?.channels()));
+            if let Ok(1) = channels {
+                warn!("Ignoring --pan, the output device is mono");
+            }
+        }
+        
+        let snooze = args.snooze.map(|seconds| Snooze::new(seconds, args.snooze_file.clone()));
+        let duplicate_window = args.skip_duplicate_window.map(Duration::from_millis);
+        // End time of the last repeat that was played, every repeat plays the same notes
+        let mut last_beep: Option<Instant> = None;
+        
+        // With --loop, Ctrl-C stops the beep after the repeat that is playing
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(tokio::sync::Notify::new());
+        if args.loop_beep {
+            let (interrupted, stop) = (interrupted.clone(), stop.clone());
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    interrupted.store(true, Ordering::Relaxed);
+                    stop.notify_one();
+                }
+            });
+        }
+        let repeats = if args.loop_beep { u32::MAX } else { args.repeats };
+        
+        for i in 0..repeats {
+            if i > 0 {
+                tokio::select! {
+                    _ = sleep(Duration::from_millis(args.delay)) => {}
+                    _ = stop.notified() => {}
+                }
+            }
+            
+            if interrupted.load(Ordering::Relaxed) {
+                if args.verbose {
+                    info!("Interrupted, stopping beep after {} repeat(s)", i);
+                }
+                break;
+            }
+            
+            if acknowledged || ack.is_some_and(AckServer::acked) {
+                info!("✓ Acknowledged, stopping beep");
+                break;
+            }
+            
+            if let Some(snooze) = snooze.as_ref().filter(|s| s.requested()) {
+                if args.verbose {
+                    info!("💤 Snoozed for {} s", snooze.duration.as_secs());
+                }
+                sleep(snooze.duration).await;
+            }
+            
+            let volume = repeat_volume(&args.repeat_volumes, i);
+            
+            if let (Some(window), Some(ended)) = (duplicate_window, last_beep) {
+                if ended.elapsed() < window {
+                    if args.verbose {
+                        info!("Skipping duplicate beep within {} ms", window.as_millis());
+                    }
+                    continue;
+                }
+            }
+            
+            if args.headless {
+                match render_note_samples(&notes, PCM_SAMPLE_RATE, tone) {
+                    Ok(samples) if args.verbose => {
+                        let peak = samples.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+                        info!("🔇 Rendered {} samples (peak {:.3})", samples.len(), peak);
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Error rendering sound: {}", e),
+                }
+                continue;
+            }
+            
+            // The whole sequence goes through one stream, so notes join without gaps or clicks
+            let result = generate_notes(&notes, tone, args.gain * volume * beep_mix, beep_output);
+            last_beep = Some(Instant::now());
+            
+            if let Err(e) = result {
+                error!("Error generating sound: {}", e);
+                // Fallback to system beep
+                if args.sound_fallback != SoundFallback::None {
+                    ring_bell(&args.bell_char);
+                }
+            } else if args.verbose {
+                match notes.as_slice() {
+                    [Note { frequency: Some(frequency), duration_ms }] => {
+                        info!("🔊 Beep {} Hz for {} ms", frequency, duration_ms);
+                    }
+                    _ => info!("🔊 Played {} notes", notes.len()),
+                }
+            }
+        }
+    }
+    
+    if let Some(task) = sound_task {
+        let _ = task.await;
+    }
+}
+
+/// Send notifications, play sounds and beep once for a single alert
+async fn run_alert(
+    args: &Args,
+    config: Option<&Config>,
+    tone: &ToneOptions,
+    message: &str,
+) -> Result<()> {
+    let default_title = config
+        .and_then(|c| c.general.as_ref())
+        .and_then(|g| g.default_title.as_deref());
+    
+    // JSON payloads are left alone, a newline inside a string would break them
+    let wrapped;
+    let message = match args.wrap {
+        Some(width) if serde_json::from_str::<Value>(message).is_err() => {
+            wrapped = wrap_text(message, width as usize);
+            wrapped.as_str()
+        }
+        _ => message,
+    };
+    
+    let with_tone;
+    let message = if args.include_tone_info {
+        with_tone = add_tone_info(message, args);
+        with_tone.as_str()
+    } else {
+        message
+    };
+    
+    let ack = match args.ack_listen {
+        Some(addr) => {
+            let server = AckServer::start(addr, args.ack_url.as_deref(), Duration::from_secs(args.ack_timeout))?;
+            if args.verbose {
+                info!("Acknowledge link: {}", server.url);
+            }
+            Some(server)
+        }
+        None => None,
+    };
+    
+    let notification = Notification {
+        title: args.title.as_deref().or(default_title),
+        ack_url: ack.as_ref().map(|a| a.url.as_str()),
+        ..Notification::from_args(message, args)
+    };
+    let webhook_meta = args.include_meta.then(|| build_webhook_meta(args, &notification));
+    
+    let beep_output = BeepOutput::new(args, config);
+    
+    // The sound repeats until the Pushover receipt is acknowledged, so it waits for the receipt
+    let dispatched = if args.repeat_until_ack {
+        let dispatched = dispatch_notifications(args, config, &notification, webhook_meta.as_ref()).await;
+        let receipt = dispatched.as_ref().ok().and_then(|d| d.receipt.as_deref());
+        play_local_alert(args, config, tone, &beep_output, ack.as_ref(), receipt).await;
+        dispatched
+    } else {
+        // Notifications go out while the beep plays; playback blocks, so it gets a thread of its own
+        let runtime = tokio::runtime::Handle::current();
+        tokio::task::block_in_place(|| {
+            std::thread::scope(|scope| {
+                let (played_tx, played_rx) = tokio::sync::oneshot::channel::<()>();
+                let local = scope.spawn(|| {
+                    runtime.block_on(play_local_alert(args, config, tone, &beep_output, ack.as_ref(), None));
+                    let _ = played_tx.send(());
+                });
+                let dispatched = runtime.block_on(async {
+                    let dispatch = dispatch_notifications(args, config, &notification, webhook_meta.as_ref());
+                    tokio::pin!(dispatch);
+                    tokio::select! {
+                        dispatched = &mut dispatch => return dispatched,
+                        // A panicking alert drops the sender, which ends the wait just the same
+                        _ = played_rx => {}
+                    }
+                    // Once the alert is over, whatever hasn't arrived gets one grace period
+                    within_grace(args.retries, dispatch).await.unwrap_or_else(|| Ok(Dispatched::default()))
+                });
+                if let Err(panic) = local.join() {
+                    std::panic::resume_unwind(panic);
+                }
+                dispatched
+            })
+        })
+    };
+    
+    if let Some(ack) = ack {
+        ack.stop().await;
+    }
+    
+    let dispatched = dispatched?;
+    // Only now, so the blips don't play over the alert
+    play_confirmations(args, config, dispatched.confirmations).await;
+    
+    // The primary provider is authoritative, the run fails with it
+    match dispatched.failed_primary {
+        Some(primary) => Err(anyhow::anyhow!("Primary provider {} failed", primary.name())),
+        None => Ok(()),
+    }
+}
+
+/// Field selected in the --tui editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TuiField {
+    Frequency,
+    Length,
+    Volume,
+    Waveform,
+    Title,
+    Message,
+    PresetName,
+}
+
+impl TuiField {
+    const ALL: [TuiField; 7] = [
+        TuiField::Frequency,
+        TuiField::Length,
+        TuiField::Volume,
+        TuiField::Waveform,
+        TuiField::Title,
+        TuiField::Message,
+        TuiField::PresetName,
+    ];
+    
+    fn label(self) -> &'static str {
+        match self {
+            TuiField::Frequency => "Frequency",
+            TuiField::Length => "Length",
+            TuiField::Volume => "Volume",
+            TuiField::Waveform => "Waveform",
+            TuiField::Title => "Notification title",
+            TuiField::Message => "Notification message",
+            TuiField::PresetName => "Preset name",
+        }
+    }
+}
+
+/// Values being edited in --tui, seeded from the command line
+struct TuiState {
+    selected: usize,
+    frequency: f32,
+    length: u64,
+    volume: f32,
+    waveform: Waveform,
+    title: String,
+    message: String,
+    preset_name: String,
+    status: String,
+}
+
+impl TuiState {
+    fn field(&self) -> TuiField {
+        TuiField::ALL[self.selected]
+    }
+    
+    /// Nudge the selected slider by `steps`, or cycle the waveform
+    fn adjust(&mut self, steps: i32) {
+        match self.field() {
+            TuiField::Frequency => {
+                self.frequency = (self.frequency + 10.0 * steps as f32).clamp(MIN_AUDIBLE_HZ, MAX_AUDIBLE_HZ);
+            }
+            TuiField::Length => {
+                self.length = (self.length as i64 + 10 * steps as i64).clamp(10, 5000) as u64;
+            }
+            TuiField::Volume => {
+                self.volume = (self.volume + 0.05 * steps as f32).clamp(0.0, 1.0);
+            }
+            TuiField::Waveform => {
+                let all = Waveform::value_variants();
+                let current = all.iter().position(|&w| w == self.waveform).unwrap_or(0) as i32;
+                self.waveform = all[(current + steps.signum()).rem_euclid(all.len() as i32) as usize];
+            }
+            _ => {}
+        }
+    }
+    
+    fn text_mut(&mut self) -> Option<&mut String> {
+        match self.field() {
+            TuiField::Title => Some(&mut self.title),
+            TuiField::Message => Some(&mut self.message),
+            TuiField::PresetName => Some(&mut self.preset_name),
+            _ => None,
+        }
+    }
+    
+    fn preset(&self) -> Preset {
+        Preset {
+            frequency: Some(PresetFrequency::Single(self.frequency)),
+            length: Some(self.length),
+            volume: Some(self.volume),
+            waveform: Some(self.waveform),
+            ..Preset::default()
+        }
+    }
+}
+
+fn waveform_name(waveform: Waveform) -> String {
+    waveform
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn draw_tui(frame: &mut ratatui::Frame, state: &TuiState) {
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::style::{Color, Modifier, Style};
+    use ratatui::widgets::{Block, Gauge, Paragraph, Wrap};
+    
+    let mut constraints = vec![Constraint::Length(3); TuiField::ALL.len()];
+    constraints.push(Constraint::Min(1));
+    constraints.push(Constraint::Length(1));
+    let areas = Layout::vertical(constraints).split(frame.area());
+    
+    for (i, &field) in TuiField::ALL.iter().enumerate() {
+        let selected = i == state.selected;
+        let border = if selected { Style::default().fg(Color::Yellow) } else { Style::default() };
+        let block = Block::bordered().title(field.label()).border_style(border);
+        
+        // Sliders are gauges, the frequency on a log scale across the audible range
+        let gauge = |ratio: f64, label: String| {
+            Gauge::default()
+                .block(block.clone())
+                .gauge_style(Style::default().fg(Color::Cyan))
+                .ratio(ratio.clamp(0.0, 1.0))
+                .label(label)
+        };
+        let cursor = if selected { "▏" } else { "" };
+        
+        match field {
+            TuiField::Frequency => {
+                let range = (MAX_AUDIBLE_HZ / MIN_AUDIBLE_HZ).ln();
+                let ratio = (state.frequency / MIN_AUDIBLE_HZ).ln() / range;
+                frame.render_widget(gauge(ratio as f64, format!("{:.0} Hz", state.frequency)), areas[i]);
+            }
+            TuiField::Length => {
+                frame.render_widget(gauge(state.length as f64 / 5000.0, format!("{} ms", state.length)), areas[i]);
+            }
+            TuiField::Volume => {
+                frame.render_widget(gauge(state.volume as f64, format!("{:.2}", state.volume)), areas[i]);
+            }
+            TuiField::Waveform => {
+                let text = format!("◀ {} ▶", waveform_name(state.waveform));
+                frame.render_widget(Paragraph::new(text).block(block), areas[i]);
+            }
+            TuiField::Title => {
+                frame.render_widget(Paragraph::new(format!("{}{}", state.title, cursor)).block(block), areas[i]);
+            }
+            TuiField::Message => {
+                frame.render_widget(Paragraph::new(format!("{}{}", state.message, cursor)).block(block), areas[i]);
+            }
+            TuiField::PresetName => {
+                frame.render_widget(Paragraph::new(format!("{}{}", state.preset_name, cursor)).block(block), areas[i]);
+            }
+        }
+    }
+    
+    let status = Paragraph::new(state.status.as_str()).wrap(Wrap { trim: true });
+    frame.render_widget(status, areas[TuiField::ALL.len()]);
+    
+    let help = "↑↓ field  ←→ adjust (PgUp/PgDn ×10)  Enter play  Ctrl-N notify  Ctrl-S save preset  Esc quit";
+    let help = Paragraph::new(help).style(Style::default().add_modifier(Modifier::DIM));
+    frame.render_widget(help, areas[TuiField::ALL.len() + 1]);
+}
+
+fn save_preset(name: &str, preset: &Preset) -> Result<PathBuf> {
+    let path = get_preset_path(name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_yaml::to_string(preset)?)?;
+    Ok(path)
+}
+
+/// Send the message being edited to every configured provider, returning a status line
+async fn send_tui_notification(args: &Args, config: Option<&Config>, state: &TuiState) -> String {
+    let Some(config) = config else {
+        return "No notification providers configured".to_string();
+    };
+    
+    let default_title = config.general.as_ref().and_then(|g| g.default_title.as_deref());
+    let notification = Notification {
+        title: Some(state.title.as_str()).filter(|t| !t.is_empty()).or(default_title),
+        ..Notification::from_args(&state.message, args)
+    };
+    
+    let mut sent = Vec::new();
+    let mut failed = Vec::new();
+    for provider in config.configured_providers() {
+        match send_notification(provider, config, &notification, None, args.insecure, args.retries, false).await {
+            Ok(_) => sent.push(provider.name()),
+            Err(e) => failed.push(format!("{} ({})", provider.name(), describe_delivery_error(&e))),
+        }
+    }
+    
+    match (sent.is_empty(), failed.is_empty()) {
+        (true, true) => "No notification providers configured".to_string(),
+        (false, true) => format!("✓ Sent via {}", sent.join(", ")),
+        (true, false) => format!("✗ Failed: {}", failed.join(", ")),
+        (false, false) => format!("✓ Sent via {}, ✗ failed: {}", sent.join(", "), failed.join(", ")),
+    }
+}
+
+/// Interactive editor to dial in a tone, test notifications and save the result as a preset
+async fn run_tui(args: &Args, config: Option<&Config>) -> Result<()> {
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    
+    let mut state = TuiState {
+        selected: 0,
+        frequency: args.frequency[0],
+        length: args.length,
+        volume: args.volume,
+        waveform: args.waveform,
+        title: args.title.clone().unwrap_or_default(),
+        message: args.data.clone().unwrap_or_else(|| "Beep!".to_string()),
+        preset_name: args.preset.clone().unwrap_or_else(|| "custom".to_string()),
+        status: "Press Enter to hear the tone".to_string(),
+    };
+    let beep_output = BeepOutput::new(args, config);
+    
+    let mut terminal = ratatui::init();
+    let result: Result<()> = loop {
+        if let Err(e) = terminal.draw(|frame| draw_tui(frame, &state)) {
+            break Err(e.into());
+        }
+        
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e.into()),
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let fields = TuiField::ALL.len();
+        
+        match key.code {
+            KeyCode::Esc => break Ok(()),
+            KeyCode::Char('c') if ctrl => break Ok(()),
+            KeyCode::Up | KeyCode::BackTab => state.selected = (state.selected + fields - 1) % fields,
+            KeyCode::Down | KeyCode::Tab => state.selected = (state.selected + 1) % fields,
+            KeyCode::Left => state.adjust(-1),
+            KeyCode::Right => state.adjust(1),
+            KeyCode::PageDown => state.adjust(-10),
+            KeyCode::PageUp => state.adjust(10),
+            KeyCode::Enter => {
+                let tone = ToneOptions {
+                    waveform: state.waveform,
+                    volume: state.volume,
+                    fade_ms: args.fade_ms,
+                    pan: args.pan,
+                    ..ToneOptions::default()
+                };
+                state.status = match generate_beep_tone(state.frequency, state.length, &tone, args.gain, &beep_output) {
+                    Ok(()) => format!("🔊 Played {:.0} Hz for {} ms", state.frequency, state.length),
+                    Err(e) => format!("Error generating sound: {}", e),
+                };
+            }
+            KeyCode::Char('n') if ctrl => {
+                state.status = "Sending…".to_string();
+                if let Err(e) = terminal.draw(|frame| draw_tui(frame, &state)) {
+                    break Err(e.into());
+                }
+                state.status = send_tui_notification(args, config, &state).await;
+            }
+            KeyCode::Char('s') if ctrl => {
+                state.status = match save_preset(&state.preset_name, &state.preset()) {
+                    Ok(path) => format!("✓ Saved preset to {} (use --preset {})", path.display(), state.preset_name),
+                    Err(e) => format!("Error saving preset: {}", e),
+                };
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = state.text_mut() {
+                    text.pop();
+                }
+            }
+            KeyCode::Char(c) if !ctrl => {
+                if let Some(text) = state.text_mut() {
+                    text.push(c);
+                }
+            }
+            _ => {}
+        }
+    };
+    
+    ratatui::restore();
+    result
+}
+
+/// Run an alert at every time matching `schedule` until Ctrl-C or SIGTERM
+async fn run_schedule(
+    schedule: &croner::Cron,
+    args: &Args,
+    config: Option<&Config>,
+    tone: &ToneOptions,
+) -> Result<()> {
+    if args.verbose {
+        info!("⏰ Running on schedule \"{}\" (Ctrl-C to stop)", schedule.pattern);
+    }
+    
+    let message = args.data.as_deref().unwrap_or("Beep!");
+    
+    loop {
+        let now = Local::now();
+        let next = schedule
+            .find_next_occurrence(&now, false)
+            .map_err(|e| anyhow::anyhow!("No upcoming time for \"{}\": {}", schedule.pattern, e))?;
+        if args.verbose {
+            info!("Next beep at {}", next.format("%Y-%m-%d %H:%M:%S"));
+        }
+        
+        let wait = (next - now).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = shutdown_signal() => break,
+            _ = sleep(wait) => {}
+        }
+        
+        // Rendered per run so {time} is when the beep fired
+        if let Err(e) = run_alert(args, config, tone, &render_message(message, args.exit_code)).await {
+            error!("Scheduled alert failed: {}", e);
+        }
+    }
+    
+    if args.verbose {
+        info!("Stopped schedule");
+    }
+    Ok(())
+}
+
+/// How often --tail re-checks the file when no change event arrives (e.g. on network filesystems)
+const TAIL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Device and inode of a file, to notice when a rotated log is replaced
+#[cfg(unix)]
+fn file_identity(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_identity(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Follows a log file like `tail -F`, through rotation and truncation
+struct LogTail {
+    path: PathBuf,
+    file: Option<fs::File>,
+    identity: Option<(u64, u64)>,
+    pos: u64,
+    /// Start of a line whose newline hasn't been written yet
+    partial: Vec<u8>,
+}
+
+impl LogTail {
+    /// Start at the end of `path`, or wait for it to be created
+    fn open(path: &Path) -> Result<Self> {
+        let mut tail = LogTail {
+            path: path.to_path_buf(),
+            file: None,
+            identity: None,
+            pos: 0,
+            partial: Vec::new(),
+        };
+        
+        match fs::File::open(path) {
+            Ok(file) => {
+                let meta = file.metadata()?;
+                tail.identity = file_identity(&meta);
+                tail.pos = meta.len();
+                tail.file = Some(file);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(tail)
+    }
+    
+    /// Complete lines written since the last call
+    fn read_lines(&mut self) -> Result<Vec<String>> {
+        let mut lines = Vec::new();
+        
+        match fs::metadata(&self.path) {
+            Ok(meta) if self.file.is_none() || file_identity(&meta) != self.identity => {
+                // Finish the rotated-away file, then follow the new one from its start
+                self.read_available(&mut lines)?;
+                self.file = Some(fs::File::open(&self.path)?);
+                self.identity = file_identity(&meta);
+                self.pos = 0;
+                self.partial.clear();
+            }
+            Ok(meta) if meta.len() < self.pos => {
+                // Truncated in place (logrotate's copytruncate)
+                self.pos = 0;
+                self.partial.clear();
+            }
+            Ok(_) => {}
+            // Rotated away and not recreated yet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        
+        self.read_available(&mut lines)?;
+        Ok(lines)
+    }
+    
+    fn read_available(&mut self, lines: &mut Vec<String>) -> Result<()> {
+        use std::io::{Read, Seek, SeekFrom};
+        
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        file.seek(SeekFrom::Start(self.pos))?;
+        let read = file.read_to_end(&mut self.partial)?;
+        self.pos += read as u64;
+        
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            lines.push(line.trim_end_matches(['\n', '\r']).to_string());
+        }
+        Ok(())
     }
-    Ok(())
 }
 
-fn generate_beep_tone(frequency: f32, duration_ms: u64) -> Result<()> {
-    let host = cpal::default_host();
-    let device = host.default_output_device()
-        .ok_or_else(|| anyhow::anyhow!("No audio device available"))?;
+/// Run an alert for every (debounced) burst of lines in `path` matching `pattern`, until Ctrl-C or SIGTERM
+async fn tail_path(
+    path: &Path,
+    pattern: &regex::Regex,
+    args: &Args,
+    config: Option<&Config>,
+    tone: &ToneOptions,
+) -> Result<()> {
+    use notify::{RecursiveMode, Watcher};
     
-    let config = device.default_output_config()?;
+    let mut tail = LogTail::open(path)?;
     
-    match config.sample_format() {
-        SampleFormat::F32 => run_beep::<f32>(&device, &config.into(), frequency, duration_ms),
-        SampleFormat::I16 => run_beep::<i16>(&device, &config.into(), frequency, duration_ms),
-        SampleFormat::U16 => run_beep::<u16>(&device, &config.into(), frequency, duration_ms),
-        _ => Err(anyhow::anyhow!("Unsupported sample format")),
+    // Watch the directory, so a log that is rotated away and recreated is still seen
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let _ = tx.send(res);
+    })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    
+    if args.verbose {
+        info!("👀 Tailing {} for /{}/ (Ctrl-C to stop)", path.display(), pattern);
+    }
+    
+    let debounce = Duration::from_millis(args.watch_debounce);
+    let matching = |tail: &mut LogTail| -> Vec<String> {
+        match tail.read_lines() {
+            Ok(lines) => lines.into_iter().filter(|line| pattern.is_match(line)).collect(),
+            Err(e) => {
+                error!("Error reading {}: {}", path.display(), e);
+                Vec::new()
+            }
+        }
+    };
+    
+    loop {
+        tokio::select! {
+            _ = shutdown_signal() => break,
+            event = rx.recv() => match event {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => error!("Watch error: {}", e),
+                None => break,
+            },
+            _ = sleep(TAIL_POLL_INTERVAL) => {}
+        }
+        
+        let mut matched = matching(&mut tail);
+        if matched.is_empty() {
+            continue;
+        }
+        
+        // A burst of matching lines (say, a stack trace) becomes one alert
+        sleep(debounce).await;
+        matched.extend(matching(&mut tail));
+        
+        let message = match &args.data {
+            Some(data) => render_message(data, args.exit_code),
+            None => matched.join("\n"),
+        };
+        
+        if args.verbose {
+            info!("📝 {}", message);
+        }
+        // One failed alert (e.g. no provider reachable) shouldn't stop following the log
+        if let Err(e) = run_alert(args, config, tone, &message).await {
+            error!("Alert for {} failed: {}", path.display(), e);
+        }
+    }
+    
+    if args.verbose {
+        info!("Stopped tailing {}", path.display());
     }
+    Ok(())
 }
 
-fn run_beep<T>(
-    device: &cpal::Device,
-    config: &cpal::StreamConfig,
-    frequency: f32,
-    duration_ms: u64,
-) -> Result<()>
-where
-    T: Sample + cpal::SizedSample + Send + 'static,
-    T: FromSample<f32>,
-{
-    let sample_rate = config.sample_rate.0 as f32;
-    let channels = config.channels as usize;
+/// Sleep for --in, counting down in verbose mode. False when cancelled with Ctrl-C
+async fn wait_for_timer(duration: Duration, verbose: bool) -> bool {
+    let deadline = tokio::time::Instant::now() + duration;
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
     
-    let mut sample_clock = 0f32;
-    let total_samples = (sample_rate * (duration_ms as f32 / 1000.0)) as usize;
-    let mut samples_played = 0;
+    if verbose {
+        info!("⏳ Beeping in {}", humantime::format_duration(duration));
+    }
     
-    let stream = device.build_output_stream(
-        config,
-        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-            for frame in data.chunks_mut(channels) {
-                if samples_played >= total_samples {
-                    for sample in frame.iter_mut() {
-                        *sample = T::EQUILIBRIUM;
-                    }
-                    continue;
+    loop {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        let remaining = (remaining.as_millis() as u64).div_ceil(1000);
+        // Announce whole minutes, then 30 and 10 seconds, then each of the last five
+        let mark = match remaining {
+            r if r > 60 => (r - 1) / 60 * 60,
+            31..=60 => 30,
+            11..=30 => 10,
+            6..=10 => 5,
+            r => r.saturating_sub(1),
+        };
+        let (wake, announce) = match verbose && mark > 0 {
+            true => (deadline - Duration::from_secs(mark), true),
+            false => (deadline, false),
+        };
+        
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                if verbose {
+                    info!("Timer cancelled");
                 }
-                
-                let value = (sample_clock * frequency * 2.0 * std::f32::consts::PI / sample_rate).sin();
-                let sample_f32 = value * 0.3; // Reduce volume
-                let sample = T::from_sample(sample_f32);
-                
-                for sample_out in frame.iter_mut() {
-                    *sample_out = sample;
+                return false;
+            }
+            _ = tokio::time::sleep_until(wake) => {}
+        }
+        
+        if !announce {
+            return true;
+        }
+        info!("⏳ {} left", humantime::format_duration(Duration::from_secs(mark)));
+    }
+}
+
+/// Notification dispatches still running, so an early exit can let them finish
+static DISPATCHES_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Signalled whenever a dispatch finishes or is dropped
+static DISPATCH_FINISHED: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+/// Counts a dispatch as in flight until it is dropped
+struct DispatchGuard;
+
+impl DispatchGuard {
+    fn new() -> Self {
+        DISPATCHES_IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        DispatchGuard
+    }
+}
+
+impl Drop for DispatchGuard {
+    fn drop(&mut self) {
+        DISPATCHES_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+        DISPATCH_FINISHED.notify_waiters();
+    }
+}
+
+/// Resolves once no dispatch is in flight
+async fn dispatches_finished() {
+    loop {
+        // Registered before the check, so a dispatch finishing in between still wakes it
+        let finished = DISPATCH_FINISHED.notified();
+        if DISPATCHES_IN_FLIGHT.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        finished.await;
+    }
+}
+
+/// Time for one provider to run through all of its --retries, each waiting up to one --timeout
+fn dispatch_grace(retries: u32) -> Duration {
+    let timeout = HTTP_TIMEOUT.get().copied().unwrap_or(DEFAULT_HTTP_TIMEOUT);
+    let backoff = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(retries) - 1);
+    timeout.saturating_mul(retries.saturating_add(1)).saturating_add(backoff)
+}
+
+/// Give notifications still in flight one grace period once the alert is over
+///
+/// Returns `None`, after logging what gets dropped, when the grace period runs out.
+async fn within_grace<T>(retries: u32, dispatch: impl std::future::Future<Output = T>) -> Option<T> {
+    let grace = dispatch_grace(retries);
+    match tokio::time::timeout(grace, dispatch).await {
+        Ok(output) => Some(output),
+        Err(_) => {
+            warn!(
+                "Dropping {} notification dispatch(es) still in flight after {} s",
+                DISPATCHES_IN_FLIGHT.load(Ordering::SeqCst),
+                grace.as_secs()
+            );
+            None
+        }
+    }
+}
+
+/// Exit on Ctrl-C during a single alert, first giving notifications in flight their grace period
+///
+/// A second Ctrl-C exits right away. `--loop` handles Ctrl-C itself, it only stops the beep.
+fn exit_on_ctrl_c(args: &Args) {
+    if args.loop_beep {
+        return;
+    }
+    let (verbose, retries) = (args.verbose, args.retries);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        if DISPATCHES_IN_FLIGHT.load(Ordering::SeqCst) > 0 {
+            if verbose {
+                info!("Waiting for notifications in flight (Ctrl-C again to quit)");
+            }
+            tokio::select! {
+                _ = within_grace(retries, dispatches_finished()) => {}
+                _ = tokio::signal::ctrl_c() => {
+                    warn!("Exiting with notifications still in flight");
                 }
-                
-                sample_clock = (sample_clock + 1.0) % sample_rate;
-                samples_played += 1;
             }
-        },
-        |err| eprintln!("Audio stream error: {}", err),
-        None,
-    )?;
+        }
+        std::process::exit(130);
+    });
+}
+
+/// Resolves on Ctrl-C, or SIGTERM where available, so the daemon stops cleanly under service managers
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Run an alert for every (debounced) change under `path` until Ctrl-C
+async fn watch_path(
+    path: &Path,
+    args: &Args,
+    config: Option<&Config>,
+    tone: &ToneOptions,
+) -> Result<()> {
+    use notify::{RecursiveMode, Watcher};
     
-    stream.play()?;
-    std::thread::sleep(Duration::from_millis(duration_ms + 50)); // Add buffer
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let _ = tx.send(res);
+    })?;
+    watcher.watch(path, RecursiveMode::Recursive)?;
+    
+    if args.verbose {
+        info!("👀 Watching {} (Ctrl-C to stop)", path.display());
+    }
     
+    let debounce = Duration::from_millis(args.watch_debounce);
+    
+    loop {
+        let event = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            event = rx.recv() => match event {
+                Some(event) => event,
+                None => break,
+            },
+        };
+        
+        let mut changed = match event {
+            Ok(event) if is_change_event(&event.kind) => event.paths,
+            Ok(_) => continue,
+            Err(e) => {
+                error!("Watch error: {}", e);
+                continue;
+            }
+        };
+        
+        // Fold bursts of events (editors often write several times) into one alert
+        loop {
+            tokio::select! {
+                _ = sleep(debounce) => break,
+                event = rx.recv() => match event {
+                    Some(Ok(event)) if is_change_event(&event.kind) => {
+                        for path in event.paths {
+                            if !changed.contains(&path) {
+                                changed.push(path);
+                            }
+                        }
+                    }
+                    Some(_) => {}
+                    None => break,
+                },
+            }
+        }
+        
+        let message = match &args.data {
+            Some(data) => render_message(data, args.exit_code),
+            None => {
+                let paths: Vec<String> = changed.iter().map(|p| p.display().to_string()).collect();
+                format!("Changed: {}", paths.join(", "))
+            }
+        };
+        
+        if args.verbose {
+            info!("📝 {}", message);
+        }
+        // One failed alert (e.g. no provider reachable) shouldn't stop the watcher
+        if let Err(e) = run_alert(args, config, tone, &message).await {
+            error!("Alert for {} failed: {}", path.display(), e);
+        }
+    }
+    
+    if args.verbose {
+        info!("Stopped watching {}", path.display());
+    }
     Ok(())
 }
 
+fn is_change_event(kind: &notify::EventKind) -> bool {
+    matches!(
+        kind,
+        notify::EventKind::Create(_) | notify::EventKind::Modify(_) | notify::EventKind::Remove(_)
+    )
+}
+
+/// Tell --verbose users which parts of an alert will run, and why the others won't
+fn report_subsystems(args: &Args, config: Option<&Config>, config_path: &Path) {
+    match config {
+        Some(_) => info!("Config: {}", config_path.display()),
+        None => info!("Config: none at {}", config_path.display()),
+    }
+    
+    let mut providers: Vec<&str> = match config {
+        Some(config) => match &config.fallback {
+            Some(chain) => chain.iter().map(|p| p.name()).collect(),
+            None => config.configured_providers().iter().map(|p| p.name()).collect(),
+        },
+        None => Vec::new(),
+    };
+    if args.webhook_url.is_some() {
+        providers.push("--webhook-url");
+    }
+    // The desktop popup shows up regardless of any fallback chain
+    let desktop = match args.desktop || config.and_then(|c| c.desktop.as_ref()).is_some_and(|d| d.enabled) {
+        true => " + desktop",
+        false => "",
+    };
+    match (providers.is_empty(), config.is_some_and(|c| c.fallback.is_some())) {
+        (true, _) if !desktop.is_empty() => info!("Notifications: desktop only"),
+        (true, _) => info!("Notifications: off, no providers configured"),
+        (false, true) => info!("Notifications: fallback {}{}", providers.join(" → "), desktop),
+        (false, false) => info!("Notifications: {}{}", providers.join(", "), desktop),
+    }
+    
+    let sound = config.and_then(|c| c.sound.as_ref());
+    match (args.builtin_sound, sound.and_then(|s| s.url.as_deref()), sound.and_then(|s| s.file.as_deref())) {
+        (Some(builtin), _, _) => info!("Sound: built-in {}", builtin.name()),
+        (None, Some(url), _) => info!("Sound: {}", url),
+        (None, None, Some(file)) => info!("Sound: {}", file),
+        (None, None, None) => info!("Sound: off, no sound file or --builtin-sound"),
+    }
+    
+    if let Some(fd) = args.output_fd {
+        info!("Beep: written to fd {}", fd);
+    } else if let Some(path) = &args.output_file {
+        info!("Beep: written to {}", path.display());
+    } else if args.no_sound {
+        info!("Beep: off (--no-sound)");
+    } else if args.headless {
+        info!("Beep: rendered only (--headless)");
+    } else {
+        info!("Beep: on");
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
     
     if args.sample_config {
         print_sample_config();
         return Ok(());
     }
     
-    let config_path = get_config_path(args.config);
+    log::set_logger(&LOGGER).map_err(|e| anyhow::anyhow!("Failed to set up logging: {}", e))?;
+    log::set_max_level(log::LevelFilter::Info);
+    HTTP_TIMEOUT.get_or_init(|| Duration::from_secs(args.timeout));
+    
+    if args.config_init {
+        let path = get_config_path(None);
+        init_config(&path)?;
+        info!("✓ Wrote sample configuration to {}", path.display());
+        return Ok(());
+    }
+    
+    let config_path = get_config_path(args.config.clone());
+    if args.check_config {
+        return check_config(&config_path);
+    }
     let config = load_config(&config_path)?;
     
-    // Prepare message
-    let message = args.data.as_deref().unwrap_or("Beep!");
-    let title = args.title.as_deref();
+    if args.dump_config {
+        let config = config.ok_or_else(|| anyhow::anyhow!("No configuration at {}", config_path.display()))?;
+        print!("{}", dump_config(&config, args.mask_secrets)?);
+        return Ok(());
+    }
     
-    // Send notifications if configured
-    if let Some(config) = &config {
-        if let Some(pushover_config) = &config.pushover {
-            if let Err(e) = send_pushover_notification(pushover_config, message, title, args.priority, args.verbose).await {
-                eprintln!("Pushover error: {}", e);
+    // Without syslog there is still the console, so carry on
+    if let Err(e) = init_syslog(args.syslog, config.as_ref().and_then(|c| c.syslog.as_ref())) {
+        error!("{}", e);
+    }
+    
+    if let Some(name) = args.preset.clone() {
+        let preset = load_preset(&name)?;
+        apply_preset(&mut args, &matches, preset);
+        if args.verbose {
+            info!("Loaded preset {}", name);
+        }
+    }
+    
+    if let Some(percent) = args.progress {
+        let (frequency, repeats) = progress_tone(percent);
+        if args.verbose {
+            info!("Progress {}%: {} beep(s) at {:.2} Hz", percent, repeats, frequency);
+        }
+        args.frequency = vec![frequency];
+        args.repeats = repeats;
+    }
+    
+    if args.semitones.is_some() || args.cents.is_some() {
+        let shifted: Vec<f32> = args.frequency
+            .iter()
+            .map(|&frequency| shift_frequency(frequency, args.semitones, args.cents))
+            .collect();
+        if args.verbose {
+            info!("Shifted {} Hz to {} Hz", format_frequencies(&args.frequency), format_frequencies(&shifted));
+        }
+        args.frequency = shifted;
+    }
+    
+    // Every member of a chord is checked, not just the first
+    for &frequency in &args.frequency {
+        if !(frequency.is_finite() && frequency > 0.0) {
+            return Err(anyhow::anyhow!("Frequency {} Hz is not a positive number", frequency));
+        }
+        check_audible("Frequency", frequency, args.allow_inaudible)?;
+    }
+    
+    if !(0.0..=1.0).contains(&args.volume) {
+        let clamped = args.volume.clamp(0.0, 1.0);
+        if args.verbose {
+            warn!("--volume {} is outside 0.0-1.0, using {}", args.volume, clamped);
+        }
+        args.volume = clamped;
+    }
+    
+    if let Some(sound_volume) = args.sound_volume.filter(|v| !(0.0..=1.0).contains(v)) {
+        let clamped = sound_volume.clamp(0.0, 1.0);
+        if args.verbose {
+            warn!("--sound-volume {} is outside 0.0-1.0, using {}", sound_volume, clamped);
+        }
+        args.sound_volume = Some(clamped);
+    }
+    
+    if args.audio_host == AudioHost::Jack && args.buffer_ms.is_some() {
+        return Err(anyhow::anyhow!(
+            "--buffer-ms can't be used with --audio-host jack, the JACK server sets the buffer size (e.g. jackd -p)"
+        ));
+    }
+    
+    if args.list_devices {
+        return print_device_list(args.audio_host, args.verbose);
+    }
+    
+    if args.latency_report {
+        return print_latency_report(args.audio_host);
+    }
+    
+    if args.check_sound {
+        return check_sound(config.as_ref().and_then(|c| c.sound.as_ref())).await;
+    }
+    
+    if args.tui {
+        return run_tui(&args, config.as_ref()).await;
+    }
+    
+    let message_from_stdin = args.data.as_deref() == Some("-");
+    if message_from_stdin && args.stdin {
+        return Err(anyhow::anyhow!("-D - and --stdin can't both read stdin"));
+    }
+    
+    // Only continue if the piped value passes the threshold
+    if let Some(threshold) = args.threshold {
+        let value = read_stdin_value()?;
+        if !threshold.matches(value) {
+            if args.verbose {
+                info!("Value {} does not match threshold, skipping", value);
             }
+            return Ok(());
         }
-        
-        if let Some(webhook_config) = &config.webhook {
-            if let Err(e) = send_webhook_notification(webhook_config, message, args.verbose).await {
-                eprintln!("Webhook error: {}", e);
+    }
+    
+    if message_from_stdin {
+        let max_chars = args.max_message_length as usize;
+        let (message, length) = read_stdin_message(max_chars)?;
+        if message.is_empty() {
+            warn!("Nothing on stdin, using the default message");
+            args.data = None;
+        } else {
+            if length > max_chars && args.verbose {
+                info!("Message from stdin truncated from {} to {} characters", length, max_chars);
             }
+            args.data = Some(message);
         }
-        
-        // Play sound file if configured
-        if let Some(sound_config) = &config.sound {
-            if let Some(url) = &sound_config.url {
-                if let Err(e) = play_sound_url(url, args.verbose).await {
-                    eprintln!("Error playing sound from URL: {}", e);
-                }
-            } else if let Some(file_path) = &sound_config.file {
-                if let Err(e) = play_sound_file(file_path, args.verbose) {
-                    eprintln!("Error playing sound file: {}", e);
-                }
+    }
+    
+    if let Some(cmd) = &args.data_cmd {
+        match run_data_cmd(cmd) {
+            Ok(output) if !output.is_empty() => args.data = Some(output),
+            Ok(_) => warn!("--data-cmd produced no output, using the default message"),
+            Err(e) => error!("{}, using the default message", e),
+        }
+    }
+    
+    if let Some(path) = &args.message_file {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read message file {}: {}", path.display(), e))?;
+        // Editors end files with a newline that isn't part of the message
+        let message = content.trim_end_matches(['\n', '\r']);
+        if message.is_empty() {
+            warn!("{} is empty, using the default message", path.display());
+        } else {
+            args.data = Some(message.to_string());
+        }
+    }
+    
+    if let Some(code) = args.exit_code {
+        if code == 0 {
+            if args.verbose {
+                info!("Exit code 0, success styling (message and priority unchanged)");
+            }
+        } else {
+            if args.data.is_none() {
+                args.data = Some(format!("Command failed with exit code {}", code));
+            }
+            // Emergency priority needs --retry and --expire, without them the bump stops at high
+            let ceiling = if args.retry.is_some() && args.expire.is_some() { 2 } else { 1 };
+            let priority = args
+                .priority
+                .or(args.severity.map(Severity::pushover_priority))
+                .unwrap_or(0);
+            let bumped = (priority + 1).min(ceiling).max(priority);
+            args.priority = Some(bumped);
+            if args.verbose {
+                info!("Exit code {}, failure styling (priority {} → {})", code, priority, bumped);
             }
         }
     }
     
-    // Play local beep if not disabled
-    if !args.no_sound {
-        for i in 0..args.repeats {
-            if i > 0 {
-                sleep(Duration::from_millis(args.delay)).await;
+    let tone = ToneOptions {
+        expr: args.expr.as_deref().map(parse_tone_expr).transpose()?,
+        melody: args.melody.as_deref().map(|m| parse_melody(m, args.bpm, args.allow_inaudible)).transpose()?,
+        waveform: args.waveform,
+        volume: args.volume,
+        target_rms: args.target_rms,
+        fade_ms: args.fade_ms,
+        // A melody brings its own notes, the chord only shapes the plain beep
+        chord: match &args.melody {
+            Some(_) => Vec::new(),
+            None if args.frequency.len() > 1 => args.frequency.clone(),
+            None => Vec::new(),
+        },
+        pan: args.pan,
+    };
+    
+    if args.verbose {
+        report_subsystems(&args, config.as_ref(), &config_path);
+    }
+    
+    // Long-running modes catch up on the queue once, before they start waiting
+    if args.watch.is_some() || args.tail.is_some() || args.cron.is_some() {
+        retry_queued(&args, config.as_ref(), i64::MAX).await;
+    }
+    
+    if let Some(path) = &args.watch {
+        return watch_path(path, &args, config.as_ref(), &tone).await;
+    }
+    
+    if let (Some(path), Some(pattern)) = (&args.tail, &args.match_regex) {
+        return tail_path(path, pattern, &args, config.as_ref(), &tone).await;
+    }
+    
+    if let Some(schedule) = &args.cron {
+        return run_schedule(schedule, &args, config.as_ref(), &tone).await;
+    }
+    
+    if args.digest {
+        let message = render_message(args.data.as_deref().unwrap_or("Beep!"), args.exit_code);
+        let pending = append_to_digest(&get_digest_path(), &message)?;
+        if args.verbose {
+            info!("Queued for digest ({} pending)", pending);
+        }
+        return Ok(());
+    }
+    
+    if args.replay {
+        return replay_last_notification(&args, config.as_ref()).await;
+    }
+    
+    if args.flush_queue {
+        let config = config.as_ref().ok_or_else(|| anyhow::anyhow!("No notification providers configured"))?;
+        let (sent, left) = flush_queue(&args, config, i64::MAX).await?;
+        if args.verbose {
+            if sent + left == 0 {
+                info!("Queue is empty, nothing to send");
+            } else {
+                info!("Delivered {} queued notification(s), {} left", sent, left);
             }
-            
-            if let Err(e) = generate_beep_tone(args.frequency, args.length) {
-                eprintln!("Error generating sound: {}", e);
-                // Fallback to system beep
-                print!("\x07");
-            } else if args.verbose {
-                println!("🔊 Beep {} Hz for {} ms", args.frequency, args.length);
+        }
+        return Ok(());
+    }
+    
+    if args.flush_digest {
+        let messages = take_digest(&get_digest_path())?;
+        if messages.is_empty() {
+            if args.verbose {
+                info!("Digest is empty, nothing to send");
             }
+            return Ok(());
+        }
+        
+        if args.verbose {
+            info!("Flushing {} digest message(s)", messages.len());
         }
+        exit_on_ctrl_c(&args);
+        let started = Local::now().timestamp();
+        let result = run_alert(&args, config.as_ref(), &tone, &messages.join("\n")).await;
+        retry_queued(&args, config.as_ref(), started).await;
+        return result;
     }
     
-    Ok(())
-}
\ No newline at end of file
+    if let Some(duration) = args.start_in {
+        if !wait_for_timer(duration, args.verbose).await {
+            return Ok(());
+        }
+    }
+    exit_on_ctrl_c(&args);
+    
+    // Earlier failures are retried once the new alert is out, so it never waits on them;
+    // whatever this alert just queued waits for the next run
+    let started = Local::now().timestamp();
+    let message = render_message(args.data.as_deref().unwrap_or("Beep!"), args.exit_code);
+    let result = run_alert(&args, config.as_ref(), &tone, &message).await;
+    retry_queued(&args, config.as_ref(), started).await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn beep(frequency: f32, duration_ms: u64, sample_rate: u32) -> Vec<f32> {
+        let note = Note { frequency: Some(frequency), duration_ms };
+        render_note_samples(&[note], sample_rate, &ToneOptions::default()).unwrap()
+    }
+    
+    #[test]
+    fn sample_count_matches_duration() {
+        assert_eq!(beep(1000.0, 250, 44_100).len(), 11_025);
+        assert_eq!(beep(1000.0, 1000, 48_000).len(), 48_000);
+        assert_eq!(beep(440.0, 10, 8_000).len(), 80);
+    }
+    
+    #[test]
+    fn zero_crossings_match_frequency() {
+        for frequency in [220.0, 440.0, 1000.0] {
+            // One second, so upward crossings count cycles per second
+            let samples = beep(frequency, 1000, 48_000);
+            let crossings = samples.windows(2).filter(|pair| pair[0] <= 0.0 && pair[1] > 0.0).count();
+            assert!(
+                (crossings as f32 - frequency).abs() <= 2.0,
+                "{} Hz tone crossed zero upward {} times",
+                frequency,
+                crossings
+            );
+        }
+    }
+    
+    #[test]
+    fn edges_are_faded() {
+        let samples = beep(1000.0, 200, 48_000);
+        let peak = samples.iter().fold(0.0f32, |peak, &v| peak.max(v.abs()));
+        assert!(peak > 0.25, "peak {} is too quiet", peak);
+        assert!(samples[0].abs() < 1e-3, "first sample is {}", samples[0]);
+        assert!(samples[samples.len() - 1].abs() < 1e-3, "last sample is {}", samples[samples.len() - 1]);
+    }
+    
+    #[test]
+    fn thresholds_parse_every_operator() {
+        let cases = [
+            (">= 5", 5.0, true),
+            ("<=5", 5.0, true),
+            ("!= 5", 5.0, false),
+            ("> 5", 5.0, false),
+            ("<5", 4.0, true),
+            ("=5", 5.0, true),
+            ("== 2.5", 2.5, true),
+        ];
+        for (threshold, value, expected) in cases {
+            assert_eq!(parse_threshold(threshold).unwrap().matches(value), expected, "{} against {}", threshold, value);
+        }
+        assert!(parse_threshold("5").is_err());
+        assert!(parse_threshold("> five").is_err());
+    }
+    
+    #[test]
+    fn locations_are_range_checked() {
+        let location = parse_location("52.23, 21.01").unwrap();
+        assert_eq!((location.lat, location.lon), (52.23, 21.01));
+        assert!(parse_location("91,0").is_err());
+        assert!(parse_location("0,-181").is_err());
+        assert!(parse_location("52.23").is_err());
+    }
+    
+    #[test]
+    fn bell_char_escapes() {
+        assert_eq!(parse_bell_char(r"\a").unwrap(), "\x07");
+        assert_eq!(parse_bell_char(r"\e[5m\x07\\").unwrap(), "\x1b[5m\x07\\");
+        assert!(parse_bell_char(r"\x80").is_err());
+        assert!(parse_bell_char(r"\q").is_err());
+        assert!(parse_bell_char("\\").is_err());
+    }
+    
+    #[test]
+    fn millis_take_numbers_and_durations() {
+        assert_eq!(parse_millis("250").unwrap(), 250);
+        assert_eq!(parse_millis("2s").unwrap(), 2000);
+        assert_eq!(parse_millis("1m30s").unwrap(), 90_000);
+        assert!(parse_millis("soon").is_err());
+    }
+    
+    #[test]
+    fn notes_follow_equal_temperament() {
+        assert!((note_to_frequency("A4", false).unwrap() - 440.0).abs() < 0.01);
+        assert!((note_to_frequency("C4", false).unwrap() - 261.63).abs() < 0.01);
+        assert_eq!(note_to_frequency("C#5", false).unwrap(), note_to_frequency("Db5", false).unwrap());
+        assert!(note_to_frequency("H4", false).is_err());
+        assert!(note_to_frequency("C40", false).is_err());
+        assert!(note_to_frequency("C-2", true).is_ok());
+    }
+    
+    #[test]
+    fn melodies_mix_beats_and_milliseconds() {
+        let notes = parse_melody("q:A4 h:R, 880:150", 120, false).unwrap();
+        let parsed: Vec<(Option<f32>, u64)> = notes.iter().map(|n| (n.frequency, n.duration_ms)).collect();
+        assert_eq!(parsed, [(Some(440.0), 500), (None, 1000), (Some(880.0), 150)]);
+        assert!(parse_melody("", 120, false).is_err());
+        assert!(parse_melody("q:A4 A4", 120, false).is_err());
+    }
+    
+    #[test]
+    fn wrapping_breaks_lines_and_long_words() {
+        assert_eq!(wrap_text("the quick brown fox", 10), "the quick\nbrown fox");
+        assert_eq!(wrap_text("abcdefghij klm", 4), "abcd\nefgh\nij\nklm");
+        assert_eq!(wrap_text("one\ntwo", 80), "one\ntwo");
+    }
+    
+    #[test]
+    fn masked_urls_keep_only_the_host() {
+        assert_eq!(mask_url("https://hooks.example.com/T000/secret?token=x"), "https://hooks.example.com/********");
+        assert_eq!(mask_url("http://127.0.0.1:8080/hook"), "http://127.0.0.1:8080/********");
+        assert_eq!(mask_url("not a url"), "********");
+    }
+    
+    #[test]
+    fn messages_fill_known_tokens_only() {
+        assert_eq!(render_message("exit {exit_code}", Some(2)), "exit 2");
+        assert_eq!(render_message("exit {exit_code}", None), "exit {exit_code}");
+        assert_eq!(render_message(r#"{"text": "{nope}"}"#, None), r#"{"text": "{nope}"}"#);
+        assert_eq!(render_message("on {hostname}", None), format!("on {}", local_hostname()));
+    }
+    
+    #[test]
+    fn routes_wrap_around_midnight() {
+        let route = |from: &str, to: &str| RouteConfig {
+            name: None,
+            from: from.to_string(),
+            to: to.to_string(),
+            providers: Vec::new(),
+        };
+        let at = |time: &str| NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+        
+        let day = route("09:00", "17:00");
+        assert!(day.contains(at("09:00")).unwrap());
+        assert!(!day.contains(at("17:00")).unwrap());
+        
+        let night = route("22:00", "07:00");
+        assert!(night.contains(at("23:30")).unwrap());
+        assert!(night.contains(at("06:59")).unwrap());
+        assert!(!night.contains(at("12:00")).unwrap());
+        
+        assert!(route("25:00", "07:00").contains(at("12:00")).is_err());
+    }
+    
+    #[test]
+    fn wav_header_describes_the_samples() {
+        let path = std::env::temp_dir().join(format!("beep-test-{}.wav", std::process::id()));
+        write_beep_wav(&path, &[0, 1, -1, i16::MAX]).unwrap();
+        let wav = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        
+        let u16_at = |i: usize| u16::from_le_bytes([wav[i], wav[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes(wav[i..i + 4].try_into().unwrap());
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!((&wav[0..4], u32_at(4), &wav[8..16]), (&b"RIFF"[..], 36 + 8, &b"WAVEfmt "[..]));
+        assert_eq!((u16_at(20), u16_at(22), u32_at(24)), (1, 1, PCM_SAMPLE_RATE));
+        assert_eq!((u32_at(28), u16_at(32), u16_at(34)), (PCM_SAMPLE_RATE * 2, 2, 16));
+        assert_eq!((&wav[36..40], u32_at(40)), (&b"data"[..], 8));
+        assert_eq!(&wav[44..], [0, 0, 1, 0, 0xff, 0xff, 0xff, 0x7f]);
+    }
+}